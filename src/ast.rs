@@ -1,4 +1,9 @@
 use std::fmt::{self};
+use std::rc::Rc;
+
+use crate::executor::environment::Closure;
+
+use crate::parsers::syntax_error::SyntaxError;
 
 // Core (Programa)
 #[derive(Debug, Clone)]
@@ -10,12 +15,28 @@ pub enum Program {
 pub enum Command {
     // Atribuição de valor
     Assignment(String, Expression, bool), // x := 5
+    // Atribuição a um campo: variável, caminho dos campos, valor e se é move
+    FieldAssignment(String, Vec<String>, Expression, bool), // p.x := 5
+    // Atribuição a um elemento: lista, índice, valor e se é move
+    IndexAssignment(Expression, Expression, Expression, bool), // xs[i] := 5
     // Bloco de definições { x := x + 5 }
     DeclarationBlock(Vec<Declaration>, Box<Command>),
     // Expression (Condicional do Loop), Box<Command> (Corpo do Loop)
     WhileLoop(Expression, Box<Command>), // while
+    // String (Variável do loop), Range (Intervalo), Box<Command> (Corpo do Loop)
+    ForLoop(String, Range, Box<Command>), // for i in 0..n do
+    // Loop com rótulo, alvo de break/continue com o mesmo nome
+    Labeled(String, Box<Command>), // externo: while ...
+    // Interrompe o loop mais interno, ou o loop com o rótulo
+    Break(Option<String>), // break [rotulo]
+    // Passa para a próxima volta do loop mais interno, ou do loop com o rótulo
+    Continue(Option<String>), // continue [rotulo]
     // Expression (Condicional do If), Box<Command> (Corpo do If), Option<Box<Command>> (Corpo do Else)
-    IfElse(Expression, Box<Command>, Box<Command>), // if ... then ... else ...
+    IfElse(Expression, Box<Command>, Option<Box<Command>>), // if ... then ... [else ...]
+    // Valor comparado e os braços: o primeiro padrão que combina escolhe o comando
+    Match(Expression, Vec<(Pattern, Command)>), // match f { Forma::Vazio => skip, _ => ... }
+    // Padrão, valor comparado, corpo executado se o padrão combina e o else
+    IfLet(Pattern, Expression, Box<Command>, Option<Box<Command>>), // if let some(x) = o then ...
     // Entrada/Saída
     IO(IOCommand), // write(...) or read(...)
     // Sequência de comandos
@@ -24,18 +45,36 @@ pub enum Command {
     // CallProcedure(CallProcedure),
     // Retorno de procedure
     Evaluate(Expression),
+    // Sai do procedimento devolvendo o valor, em qualquer profundidade
+    Return(Option<Expression>), // return x
+    // Comando com a sua posição no código fonte
+    Located(Span, Box<Command>),
+    // Trecho que não pôde ser lido, no lugar do comando
+    Error(SyntaxError),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Declaration {
-    // Definição de variável única
-    Variable(String, Expression, bool), // var x = 5
+    // Definição de variável única, com o tipo opcional
+    Variable(String, Option<Type>, Expression, bool), // var x = 5, var xs: [int] = []
+    // Uma variável para cada componente de uma tupla
+    Destructuring(Vec<String>, Expression, bool), // var (q, r) = divmod(a, b)
     // Definição de variável separadas por virgula.
     Procedure(String, Vec<ProcedureParameter>, Option<Type>, Box<Command>),
+    // Definição de struct: nome e campos
+    Struct(String, Vec<StructField>), // struct Ponto { int x, int y }
+    // Definição de enum: nome e variantes, cada uma com os tipos dos seus valores
+    Enum(String, Vec<EnumVariant>), // enum Forma { Circulo(int), Vazio }
     Compound(Box<Declaration>, Box<Declaration>), // var x = 5; var y = 10;
-                                                  // MELHORIAS FUTURAS
-                                                  // Definição de varias variáveis
-                                                  // CompoundDeclaration(Vec<Box<Declaration>>), // var x = 5; var y = 10;
+    // Declaração com a sua posição no código fonte
+    Located(Span, Box<Declaration>),
+    // Trecho que não pôde ser lido, no lugar da declaração
+    Error(SyntaxError),
+    // Procedimento com os seus comentários de documentação (///)
+    Documented(String, Box<Declaration>),
+    // MELHORIAS FUTURAS
+    // Definição de varias variáveis
+    // CompoundDeclaration(Vec<Box<Declaration>>), // var x = 5; var y = 10;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -51,7 +90,135 @@ pub enum Expression {
     // BinaryOperator -> Tipo da expressão
     BinaryExp(BinaryOperator, Box<Expression>, Box<Expression>), // x + y, x - y, x == y
     CallProcedure(CallProcedure),
+    // Literal de struct: nome e o valor de cada campo
+    StructLiteral(String, Vec<(String, Expression)>), // Ponto { x: 1, y: 2 }
+    // Leitura de um campo, ou de um componente de tupla pela posição
+    FieldAccess(Box<Expression>, String), // p.x, t.0
+    // Literal de lista
+    ListLiteral(Vec<Expression>), // [1, 2, 3]
+    // Leitura de um elemento: lista e índice
+    Index(Box<Expression>, Box<Expression>), // xs[i]
+    // Literal de tupla, com pelo menos dois componentes
+    TupleLiteral(Vec<Expression>), // (1, "a")
+    // Variante de enum: enum, variante e os valores que ela carrega
+    EnumVariant(String, String, Vec<Expression>), // Forma::Circulo(3)
+    // Valor comparado e os braços: o primeiro padrão que combina escolhe a expressão
+    Match(Box<Expression>, Vec<(Pattern, Expression)>), // match f { Forma::Circulo(r) => r, _ => 0 }
+    // Option com um valor
+    Some(Box<Expression>), // some(3)
+    // Option sem valor, com o tipo dado pelo contexto
+    None, // none
+    // Padrão, valor comparado, valor se o padrão combina e valor do else
+    IfLet(Pattern, Box<Expression>, Box<Expression>, Box<Expression>), // if let some(x) = o then x else 0
+    // Procedimento anônimo: parâmetros, tipo de retorno e corpo
+    Lambda(Vec<ProcedureParameter>, Option<Type>, Box<Command>), // proc(int x) int { x * 2 }
+    // Chamada de um procedimento guardado em um valor
+    Call(Box<Expression>, Vec<Expression>), // fs[0](1), somador(1)(2)
+    // Condição, valor do then e valor do else
+    IfElse(Box<Expression>, Box<Expression>, Box<Expression>), // if a > b then a else b
     // ProcedureCall(String, Box<ExpressionList>)
+    // Expressão com a sua posição no código fonte
+    Located(Span, Box<Expression>),
+}
+
+// Trecho do código fonte, em bytes: [start, end)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    // Trecho que vai do início de um até o fim do outro
+    pub fn merge(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl Command {
+    // Ignora as posições e devolve o comando propriamente dito
+    pub fn node(&self) -> &Command {
+        match self {
+            Command::Located(_, cmd) => cmd.node(),
+            cmd => cmd,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Command::Located(span, _) => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+impl Declaration {
+    // Ignora as posições e a documentação e devolve a declaração propriamente dita
+    pub fn node(&self) -> &Declaration {
+        match self {
+            Declaration::Located(_, decl) | Declaration::Documented(_, decl) => decl.node(),
+            decl => decl,
+        }
+    }
+
+    // Texto dos comentários de documentação, sem os marcadores "///"
+    pub fn doc(&self) -> Option<&str> {
+        match self {
+            Declaration::Located(_, decl) => decl.doc(),
+            Declaration::Documented(doc, _) => Some(doc),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Declaration::Located(span, _) => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+impl Expression {
+    // Variável e caminho de campos de uma expressão como "p" ou "p.dono.nome"
+    pub fn field_path(&self) -> Option<(String, Vec<String>)> {
+        match self.node() {
+            Expression::Identifier(var) => Some((var.clone(), Vec::new())),
+            Expression::FieldAccess(object, field) => {
+                let (var, mut path) = object.field_path()?;
+                path.push(field.clone());
+                Some((var, path))
+            }
+            _ => None,
+        }
+    }
+
+    // Expressão que indica onde um valor está guardado: variável, campo ou elemento
+    pub fn is_place(&self) -> bool {
+        match self.node() {
+            Expression::Identifier(_) => true,
+            Expression::FieldAccess(object, _) | Expression::Index(object, _) => object.is_place(),
+            _ => false,
+        }
+    }
+
+    // Ignora as posições e devolve a expressão propriamente dita
+    pub fn node(&self) -> &Expression {
+        match self {
+            Expression::Located(_, expr) => expr.node(),
+            expr => expr,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expression::Located(span, _) => Some(*span),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,6 +226,7 @@ pub enum UnaryOperator {
     Neg,    // Negação
     Not,    // Negação lógica
     Length, // Tamanho da string/lista
+    BitNot, // Complemento bit a bit
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -66,9 +234,21 @@ pub enum BinaryOperator {
     // Aritméticos
     Add, // +
     Sub, // -
+    Mul, // *
+    Div, // /
+    Mod, // %
+    Pow, // **
+
+    // Bit a bit
+    BitAnd, // &
+    BitOr,  // |
+    BitXor, // ^
+    Shl,    // <<
+    Shr,    // >>
 
     // Comparação
-    Equal, // ==
+    Equal,    // ==
+    NotEqual, // !=
 
     // Lógicos
     And, // &&
@@ -108,6 +288,18 @@ pub enum Value {
     Str(String),
     // Booleano
     Bool(bool),
+    // Struct: nome e campos, na ordem da declaração
+    Struct(String, Vec<(String, Value)>),
+    // Lista, dona dos seus elementos
+    List(Vec<Value>),
+    // Tupla: componentes na ordem
+    Tuple(Vec<Value>),
+    // Variante de enum: enum, variante e os valores que ela carrega
+    Enum(String, String, Vec<Value>),
+    // Option: some(valor) ou none
+    Option(Option<Box<Value>>),
+    // Procedimento com o ambiente em que foi criado
+    Closure(Rc<Closure>),
     Unit,
 }
 
@@ -115,13 +307,54 @@ impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Int(i) => write!(f, "{}", i),
-            Value::Str(s) => write!(f, "\"{}\"", s),
+            Value::Str(s) => write!(f, "\"{}\"", escape(s)),
             Value::Bool(b) => write!(f, "{}", b),
+            Value::Struct(name, fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, value))
+                    .collect();
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            }
+            Value::List(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Value::Tuple(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "({})", values.join(", "))
+            }
+            Value::Enum(name, variant, values) if values.is_empty() => {
+                write!(f, "{}::{}", name, variant)
+            }
+            Value::Enum(name, variant, values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "{}::{}({})", name, variant, values.join(", "))
+            }
+            Value::Option(Some(value)) => write!(f, "some({})", value),
+            Value::Option(None) => write!(f, "none"),
+            Value::Closure(closure) => write!(f, "<{}>", closure.r#type()),
             Value::Unit => write!(f, "",),
         }
     }
 }
 
+// Escreve a string como um literal da linguagem, com os mesmos escapes do lexer
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 // #[derive(Debug, Clone, PartialEq, Eq)]
 // pub enum ExpressionList {
 //     // Lista de expressões
@@ -135,6 +368,70 @@ pub struct CallProcedure {
     pub args: Vec<Expression>,
 }
 
+// Intervalo do for: start..end ou start..=end, com passo opcional (1 por padrão)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub start: Expression,
+    pub end: Expression,
+    pub inclusive: bool,
+    pub step: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructField {
+    pub identifier: String,
+    pub r#type: Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumVariant {
+    pub identifier: String,
+    pub payload: Vec<Type>,
+}
+
+// Padrão de um braço do match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    // Combina com qualquer valor sem guardá-lo
+    Wildcard, // _
+    // Combina com qualquer valor e o guarda na variável
+    Binding(String), // x
+    // Combina só com o valor literal
+    Literal(Value),      // 0, true, "a"
+    Tuple(Vec<Pattern>), // (a, _)
+    // Variante de enum, com um padrão para cada valor que ela carrega
+    Variant(String, String, Vec<Pattern>), // Forma::Circulo(r)
+    // Option com um valor que combina com o padrão interno
+    Some(Box<Pattern>), // some(x)
+    None,               // none
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |patterns: &[Pattern]| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name),
+            Pattern::Literal(value) => write!(f, "{}", value),
+            Pattern::Tuple(patterns) => write!(f, "({})", join(patterns)),
+            Pattern::Variant(name, variant, patterns) if patterns.is_empty() => {
+                write!(f, "{}::{}", name, variant)
+            }
+            Pattern::Variant(name, variant, patterns) => {
+                write!(f, "{}::{}({})", name, variant, join(patterns))
+            }
+            Pattern::Some(pattern) => write!(f, "some({})", pattern),
+            Pattern::None => write!(f, "none"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ProcedureParameter {
     pub identifier: String,
@@ -147,4 +444,39 @@ pub enum Type {
     Str,
     Bool,
     Unit,
+    // Struct ou enum declarado pelo usuário, pelo nome
+    Named(String),
+    // Lista com elementos do tipo dado
+    List(Box<Type>),
+    // Tupla com os tipos dos componentes
+    Tuple(Vec<Type>),
+    // Option: um valor do tipo dado ou nenhum
+    Option(Box<Type>),
+    // Procedimento: tipos dos parâmetros e do retorno, unit se não devolve valor
+    Proc(Vec<Type>, Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Str => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Unit => write!(f, "unit"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::List(element) => write!(f, "[{}]", element),
+            Type::Tuple(components) => {
+                let components: Vec<String> = components.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", components.join(", "))
+            }
+            Type::Option(inner) => write!(f, "option<{}>", inner),
+            Type::Proc(params, return_type) => {
+                let params: Vec<String> = params.iter().map(|t| t.to_string()).collect();
+                match **return_type {
+                    Type::Unit => write!(f, "proc({})", params.join(", ")),
+                    _ => write!(f, "proc({}) -> {}", params.join(", "), return_type),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::fmt;
+
+use crate::ast::Span;
+
+// Mensagem de erro com a posição (opcional) do trecho que a causou
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Option<Span>) -> Self {
+        Diagnostic {
+            message: message.into(),
+            span,
+        }
+    }
+
+    // Formata o erro no estilo do rustc, sublinhando o trecho com '^':
+    //
+    // erro: Variável 'y' não declarada
+    //  --> programa.plp:3:5
+    //   |
+    // 3 |     y := 2
+    //   |     ^^^^^^
+    pub fn render(&self, source: &str, path: &str) -> String {
+        let span = match self.span {
+            Some(span) => span,
+            None => return format!("erro: {}", self.message),
+        };
+
+        let start = span.start.min(source.len());
+        let (line, column) = line_col(source, start);
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = &source[line_start..line_end];
+
+        // O sublinhado vai até o fim do trecho ou até o fim da linha
+        let width = source[start..span.end.clamp(start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        format!(
+            "erro: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self.message,
+            gutter,
+            path,
+            line,
+            column,
+            gutter,
+            number,
+            text,
+            gutter,
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// Converte um deslocamento em bytes para linha e coluna (começando em 1)
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |i| &before[i + 1..])
+        .chars()
+        .count()
+        + 1;
+
+    (line, column)
+}
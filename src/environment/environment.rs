@@ -1,12 +1,21 @@
-use crate::ast::{ProcedureParameter, Type};
+use crate::ast::{EnumVariant, ProcedureParameter, StructField, Type};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct VariableInfo {
     pub type_: Type,
     pub moved: bool,
+    // Campos movidos para fora da struct, como caminhos: "nome", "dono.nome"
+    pub moved_fields: HashSet<String>,
+}
+
+// O que um nome indica no escopo mais próximo em que foi declarado
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding {
+    Variable(VariableInfo),
+    Procedure(Vec<ProcedureParameter>, Option<Type>),
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +30,18 @@ pub struct Environment {
     // Parametros
     pub procedures: HashMap<String, (Vec<ProcedureParameter>, Option<Type>)>,
 
+    // Structs
+    // Campos com os seus tipos
+    pub structs: HashMap<String, Vec<StructField>>,
+
+    // Enums
+    // Variantes com os tipos dos seus valores
+    pub enums: HashMap<String, Vec<EnumVariant>>,
+
+    // Variáveis deste escopo capturadas por procedimentos, com quem as capturou.
+    // Não podem mais ser movidas, já que o procedimento pode ser chamado depois
+    pub captured: HashMap<String, String>,
+
     // Blocos aninhados
     pub parent: Option<Rc<RefCell<Environment>>>,
 }
@@ -30,6 +51,9 @@ impl Environment {
         Rc::new(RefCell::new(Self {
             variables: HashMap::new(),
             procedures: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            captured: HashMap::new(),
             parent: None,
         }))
     }
@@ -38,6 +62,9 @@ impl Environment {
         Rc::new(RefCell::new(Self {
             variables: HashMap::new(),
             procedures: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            captured: HashMap::new(),
             parent: Some(parent.clone()), // Mantém referência ao escopo pai
         }))
     }
@@ -51,6 +78,20 @@ impl Environment {
         })
     }
 
+    // Buscar o nome em todos os escopos, parando no primeiro que declara uma
+    // variável ou um procedimento com ele
+    pub fn lookup_binding(&self, name: &str) -> Option<Binding> {
+        if let Some(info) = self.variables.get(name) {
+            return Some(Binding::Variable(info.clone()));
+        }
+        if let Some((params, return_type)) = self.procedures.get(name) {
+            return Some(Binding::Procedure(params.clone(), return_type.clone()));
+        }
+        self.parent
+            .as_ref()
+            .and_then(|parent| parent.borrow().lookup_binding(name))
+    }
+
     // Buscar funcoes em todos os escopos
     pub fn lookup_procedure(&self, name: &str) -> Option<(Vec<ProcedureParameter>, Option<Type>)> {
         self.procedures.get(name).cloned().or_else(|| {
@@ -59,4 +100,22 @@ impl Environment {
                 .and_then(|parent| parent.borrow().lookup_procedure(name))
         })
     }
+
+    // Buscar structs em todos os escopos
+    pub fn lookup_struct(&self, name: &str) -> Option<Vec<StructField>> {
+        self.structs.get(name).cloned().or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().lookup_struct(name))
+        })
+    }
+
+    // Buscar enums em todos os escopos
+    pub fn lookup_enum(&self, name: &str) -> Option<Vec<EnumVariant>> {
+        self.enums.get(name).cloned().or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().lookup_enum(name))
+        })
+    }
 }
@@ -1,6 +1,7 @@
-use crate::ast::{Command, ProcedureParameter, Type, Value};
+use crate::ast::{Command, ProcedureParameter, StructField, Type, Value};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
@@ -9,11 +10,50 @@ pub struct RuntimeVariable {
     pub moved: bool,
 }
 
+// Procedimento como valor: parâmetros, tipo de retorno, corpo e o ambiente
+// em que foi criado, onde as suas variáveis livres são procuradas
+pub struct Closure {
+    pub params: Vec<ProcedureParameter>,
+    pub return_type: Option<Type>,
+    pub body: Command,
+    pub env: Rc<RefCell<RuntimeEnvironment>>,
+}
+
+impl Closure {
+    pub fn r#type(&self) -> Type {
+        Type::Proc(
+            self.params.iter().map(|p| p.r#type.clone()).collect(),
+            Box::new(self.return_type.clone().unwrap_or(Type::Unit)),
+        )
+    }
+}
+
+// O ambiente pode guardar o próprio closure, então ele não é mostrado
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closure({})", self.r#type())
+    }
+}
+
+// Dois closures só são iguais se forem o mesmo valor
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Closure {}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeEnvironment {
     pub variables: HashMap<String, RuntimeVariable>,
-    pub procedures: HashMap<String, (Vec<ProcedureParameter>, Option<Type>, Command)>,
+    // Procedimentos declarados, cada um com o ambiente da declaração
+    pub procedures: HashMap<String, Rc<Closure>>,
+    pub structs: HashMap<String, Vec<StructField>>,
     pub parent: Option<Rc<RefCell<RuntimeEnvironment>>>,
+    // Guardado por algum closure: as declarações seguintes vão para um escopo novo,
+    // então o closure só enxerga o que já existia quando foi criado
+    pub captured: bool,
 }
 
 impl RuntimeEnvironment {
@@ -21,7 +61,9 @@ impl RuntimeEnvironment {
         Rc::new(RefCell::new(Self {
             variables: HashMap::new(),
             procedures: HashMap::new(),
+            structs: HashMap::new(),
             parent: None,
+            captured: false,
         }))
     }
 
@@ -29,7 +71,9 @@ impl RuntimeEnvironment {
         Rc::new(RefCell::new(Self {
             variables: HashMap::new(),
             procedures: HashMap::new(),
+            structs: HashMap::new(),
             parent: Some(parent.clone()), // Mantém referência ao escopo pai
+            captured: false,
         }))
     }
 
@@ -42,11 +86,22 @@ impl RuntimeEnvironment {
         })
     }
 
+    // Valor do nome no escopo mais próximo que o declara: uma variável ou um
+    // procedimento declarado
+    pub fn lookup_value(&self, name: &str) -> Option<Value> {
+        if let Some(variable) = self.variables.get(name) {
+            return Some(variable.value.clone());
+        }
+        if let Some(closure) = self.procedures.get(name) {
+            return Some(Value::Closure(closure.clone()));
+        }
+        self.parent
+            .as_ref()
+            .and_then(|parent| parent.borrow().lookup_value(name))
+    }
+
     // Buscar funcoes em todos os escopos
-    pub fn lookup_procedure(
-        &self,
-        name: &str,
-    ) -> Option<(Vec<ProcedureParameter>, Option<Type>, Command)> {
+    pub fn lookup_procedure(&self, name: &str) -> Option<Rc<Closure>> {
         self.procedures
             .get(name)
             .cloned()
@@ -55,4 +110,13 @@ impl RuntimeEnvironment {
                 None => None,
             })
     }
+
+    // Buscar structs em todos os escopos
+    pub fn lookup_struct(&self, name: &str) -> Option<Vec<StructField>> {
+        self.structs.get(name).cloned().or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent.borrow().lookup_struct(name))
+        })
+    }
 }
@@ -3,16 +3,38 @@ use std::rc::Rc;
 
 use crate::ast::{
     BinaryOperator, CallProcedure, Command, ConcreteValue, Declaration, Expression, IOCommand,
-    Program, UnaryOperator, Value,
+    Pattern, Program, Range, Span, UnaryOperator, Value,
 };
 
+use crate::diagnostics::diagnostics::Diagnostic;
+use crate::executor::environment::Closure;
 use crate::executor::environment::RuntimeEnvironment;
 use crate::executor::environment::RuntimeVariable;
 
+// Desvio pendente: os comandos seguintes são pulados até o loop de destino tratá-lo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Jump {
+    Break(Option<String>),
+    Continue(Option<String>),
+    Return(Value),
+}
+
 #[derive(Debug, Clone)]
 pub struct Executor {
     pub env: Rc<RefCell<RuntimeEnvironment>>,
-    pub errors: Vec<String>,
+    pub errors: Vec<Diagnostic>,
+    // Posição do nó sendo executado, usada nas mensagens de erro
+    pub span: Option<Span>,
+    // break/continue ainda não tratado por um loop
+    pub jump: Option<Jump>,
+    // Valor da expressão que termina o último comando executado, se houver
+    pub tail_value: Option<Value>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Executor {
@@ -20,10 +42,13 @@ impl Executor {
         Executor {
             env: RuntimeEnvironment::new(),
             errors: Vec::new(),
+            span: None,
+            jump: None,
+            tail_value: None,
         }
     }
 
-    pub fn execute_program(&mut self, program: &Program) -> Result<(), Vec<String>> {
+    pub fn execute_program(&mut self, program: &Program) -> Result<(), Vec<Diagnostic>> {
         match program {
             Program::Command(cmd) => self.execute_command(cmd),
         }
@@ -35,13 +60,58 @@ impl Executor {
     }
 
     pub fn execute_command(&mut self, cmd: &Command) {
+        // Após um erro de execução o restante do programa não é executado,
+        // e após um break/continue o restante do corpo do loop também não
+        if !self.errors.is_empty() || self.jump.is_some() {
+            return;
+        }
+
         match cmd {
             Command::Assignment(var, expr, is_move) => self.execute_assignment(var, expr, is_move),
+            Command::FieldAssignment(var, path, expr, is_move) => {
+                self.execute_field_assignment(var, path, expr, *is_move)
+            }
+            Command::IndexAssignment(list, index, expr, is_move) => {
+                self.execute_index_assignment(list, index, expr, *is_move)
+            }
             Command::DeclarationBlock(decls, body) => self.execute_declaration_block(decls, body),
-            Command::WhileLoop(condition, body) => self.execute_while_loop(condition, body),
+            Command::WhileLoop(condition, body) => self.execute_while_loop(None, condition, body),
+            Command::ForLoop(var, range, body) => self.execute_for_loop(None, var, range, body),
+            Command::Labeled(label, cmd) => match cmd.node() {
+                Command::WhileLoop(condition, body) => {
+                    self.execute_while_loop(Some(label), condition, body)
+                }
+                Command::ForLoop(var, range, body) => {
+                    self.execute_for_loop(Some(label), var, range, body)
+                }
+                _ => self.execute_command(cmd),
+            },
+            Command::Break(label) => self.jump = Some(Jump::Break(label.clone())),
+            Command::Continue(label) => self.jump = Some(Jump::Continue(label.clone())),
+            Command::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.execute_expression(expr),
+                    None => Value::Unit,
+                };
+                self.jump = Some(Jump::Return(value));
+            }
             Command::IfElse(cond, then_cmd, else_cmd) => {
                 self.execute_if_else(cond, then_cmd, else_cmd)
             }
+            Command::Match(value, arms) => {
+                self.execute_match(value, arms, |executor, body| executor.execute_command(body));
+            }
+            Command::IfLet(pattern, value, then_cmd, else_cmd) => {
+                let arms = [
+                    (pattern.clone(), Some(then_cmd.as_ref())),
+                    (Pattern::Wildcard, else_cmd.as_deref()),
+                ];
+                self.execute_match(value, &arms, |executor, body| {
+                    if let Some(body) = body {
+                        executor.execute_command(body)
+                    }
+                });
+            }
             Command::IO(io_command) => self.execute_io(io_command),
             Command::Sequence(cmd1, cmd2) => {
                 self.execute_command(cmd1);
@@ -49,8 +119,27 @@ impl Executor {
             }
             Command::Skip => {}
             Command::Evaluate(expr) => {
-                self.execute_expression(expr);
+                let value = self.execute_expression(expr);
+                self.tail_value = Some(value);
             }
+            Command::Located(span, cmd) => self.at(*span, |executor| executor.execute_command(cmd)),
+            Command::Error(error) => {
+                self.runtime_error(format!("Comando com erro de sintaxe: {}", error));
+            }
+        }
+
+        // Só uma expressão no fim de uma sequência, bloco ou ramo de if deixa um valor
+        if !matches!(
+            cmd,
+            Command::Evaluate(_)
+                | Command::Sequence(..)
+                | Command::DeclarationBlock(..)
+                | Command::IfElse(_, _, Some(_))
+                | Command::Match(..)
+                | Command::IfLet(_, _, _, Some(_))
+                | Command::Located(..)
+        ) {
+            self.tail_value = None;
         }
     }
 
@@ -71,10 +160,8 @@ impl Executor {
             };
 
             if found {
-                if *is_move {
-                    if let Expression::Identifier(source_var) = expr {
-                        self.remove_variable(source_var);
-                    }
+                if let (true, Expression::Identifier(source_var)) = (*is_move, expr.node()) {
+                    self.remove_variable(source_var);
                 }
                 return;
             }
@@ -87,7 +174,7 @@ impl Executor {
             if let Some(parent) = parent_env {
                 current_env = parent;
             } else {
-                self.errors.push(format!(
+                self.runtime_error(format!(
                     "Atribuição inválida. Variável '{}' não declarada.",
                     var
                 ));
@@ -96,6 +183,116 @@ impl Executor {
         }
     }
 
+    pub fn execute_field_assignment(
+        &mut self,
+        var: &str,
+        path: &[String],
+        expr: &Expression,
+        is_move: bool,
+    ) {
+        let value = self.execute_expression(expr);
+        if let (true, Expression::Identifier(source_var)) = (is_move, expr.node()) {
+            self.remove_variable(source_var);
+        }
+
+        let steps: Vec<Step> = path.iter().cloned().map(Step::Field).collect();
+        self.update_place(var, &steps, |target| {
+            *target = value;
+            Ok(())
+        });
+    }
+
+    pub fn execute_index_assignment(
+        &mut self,
+        list: &Expression,
+        index: &Expression,
+        expr: &Expression,
+        is_move: bool,
+    ) {
+        let Some((var, mut steps)) = self.resolve_place(list) else {
+            return;
+        };
+        let Some(index) = self.execute_index(index) else {
+            return;
+        };
+        steps.push(Step::Index(index));
+
+        let value = self.execute_expression(expr);
+        if let (true, Expression::Identifier(source_var)) = (is_move, expr.node()) {
+            self.remove_variable(source_var);
+        }
+
+        self.update_place(&var, &steps, |target| {
+            *target = value;
+            Ok(())
+        });
+    }
+
+    // Variável e caminho até o lugar indicado pela expressão, com os índices já avaliados
+    fn resolve_place(&mut self, expr: &Expression) -> Option<(String, Vec<Step>)> {
+        match expr.node() {
+            Expression::Identifier(var) => Some((var.clone(), Vec::new())),
+            Expression::FieldAccess(object, field) => {
+                let (var, mut steps) = self.resolve_place(object)?;
+                steps.push(Step::Field(field.clone()));
+                Some((var, steps))
+            }
+            Expression::Index(list, index) => {
+                let (var, mut steps) = self.resolve_place(list)?;
+                steps.push(Step::Index(self.execute_index(index)?));
+                Some((var, steps))
+            }
+            _ => {
+                self.runtime_error("Expressão não indica uma variável, campo ou elemento");
+                None
+            }
+        }
+    }
+
+    fn execute_index(&mut self, index: &Expression) -> Option<i64> {
+        match self.execute_expression(index) {
+            Value::Int(index) => Some(index),
+            value => {
+                self.runtime_error(format!("Invalid type for list index: {:?}", value));
+                None
+            }
+        }
+    }
+
+    // Altera o valor guardado no lugar, no escopo em que a variável foi declarada
+    fn update_place<T>(
+        &mut self,
+        var: &str,
+        steps: &[Step],
+        update: impl FnOnce(&mut Value) -> Result<T, String>,
+    ) -> Option<T> {
+        let mut current_env = Some(self.env.clone());
+        while let Some(env) = current_env {
+            if !env.borrow().variables.contains_key(var) {
+                current_env = env.borrow().parent.clone();
+                continue;
+            }
+
+            let mut scope = env.borrow_mut();
+            let variable = scope.variables.get_mut(var)?;
+            let result = place_mut(&mut variable.value, steps).and_then(update);
+            drop(scope);
+            return match result {
+                Ok(value) => Some(value),
+                Err(message) => {
+                    self.runtime_error(message);
+                    None
+                }
+            };
+        }
+
+        self.runtime_error(format!(
+            "Atribuição inválida. Variável '{}' não declarada.",
+            var
+        ));
+        None
+    }
+
     pub fn execute_declaration_block(&mut self, decls: &[Declaration], body: &Command) {
         let old_env = self.env.clone();
         self.env = RuntimeEnvironment::nest(&old_env);
@@ -110,15 +307,14 @@ impl Executor {
 
     pub fn execute_declaration(&mut self, decl: &Declaration) {
         match decl {
-            Declaration::Variable(name, expr, is_moved) => {
+            Declaration::Variable(name, _, expr, is_moved) => {
                 let value = self.execute_expression(expr);
 
-                if *is_moved {
-                    if let Expression::Identifier(source_var) = expr {
-                        self.remove_variable(source_var);
-                    }
+                if let (true, Expression::Identifier(source_var)) = (*is_moved, expr.node()) {
+                    self.remove_variable(source_var);
                 }
 
+                self.declaration_scope();
                 self.env.borrow_mut().variables.insert(
                     name.clone(),
                     RuntimeVariable {
@@ -127,27 +323,172 @@ impl Executor {
                     },
                 );
             }
+            // O corpo enxerga o escopo da declaração, que inclui o próprio procedimento
             Declaration::Procedure(name, params, return_type, body) => {
-                self.env.borrow_mut().procedures.insert(
-                    name.clone(),
-                    (params.clone(), return_type.clone(), *body.clone()),
-                );
+                self.declaration_scope();
+                let closure = Closure {
+                    params: params.clone(),
+                    return_type: return_type.clone(),
+                    body: *body.clone(),
+                    env: self.capture_env(),
+                };
+                self.env
+                    .borrow_mut()
+                    .procedures
+                    .insert(name.clone(), Rc::new(closure));
+            }
+            Declaration::Destructuring(names, expr, is_moved) => {
+                let value = self.execute_expression(expr);
+
+                if let (true, Expression::Identifier(source_var)) = (*is_moved, expr.node()) {
+                    self.remove_variable(source_var);
+                }
+
+                self.declaration_scope();
+                let Value::Tuple(components) = value else {
+                    self.runtime_error(format!("Invalid type for destructuring: {:?}", value));
+                    return;
+                };
+                for (name, value) in names.iter().zip(components) {
+                    self.env.borrow_mut().variables.insert(
+                        name.clone(),
+                        RuntimeVariable {
+                            value,
+                            moved: false,
+                        },
+                    );
+                }
+            }
+            Declaration::Struct(name, fields) => {
+                self.env
+                    .borrow_mut()
+                    .structs
+                    .insert(name.clone(), fields.clone());
             }
+            // Os enums só interessam ao analisador: os valores já carregam a variante
+            Declaration::Enum(..) => {}
             Declaration::Compound(decl_1, decl_2) => {
                 self.execute_declaration(decl_1);
                 self.execute_declaration(decl_2);
+            }
+            Declaration::Located(span, decl) => {
+                self.at(*span, |executor| executor.execute_declaration(decl))
+            }
+            Declaration::Documented(_, decl) => self.execute_declaration(decl),
+            Declaration::Error(error) => {
+                self.runtime_error(format!("Declaração com erro de sintaxe: {}", error));
             } // _ => panic!("Error executing declaration [NOT SUPPORTED]: {:?}", decl),
         }
     }
 
-    pub fn execute_while_loop(&mut self, condition: &Expression, body: &Command) {
-        loop {
+    pub fn execute_while_loop(
+        &mut self,
+        label: Option<&str>,
+        condition: &Expression,
+        body: &Command,
+    ) {
+        // Um erro de execução no corpo interrompe o loop
+        while self.errors.is_empty() {
             let condition_result = self.execute_expression(condition);
             match condition_result {
-                Value::Bool(true) => self.execute_command(body),
+                Value::Bool(true) => {
+                    self.execute_command(body);
+                    if self.loop_should_stop(label) {
+                        break;
+                    }
+                }
                 Value::Bool(false) => break,
-                _ => panic!("Invalid type for WHILE condition: {:?}", condition_result),
+                _ => {
+                    self.runtime_error(format!(
+                        "Invalid type for WHILE condition: {:?}",
+                        condition_result
+                    ));
+                }
+            }
+        }
+    }
+
+    pub fn execute_for_loop(
+        &mut self,
+        label: Option<&str>,
+        var: &str,
+        range: &Range,
+        body: &Command,
+    ) {
+        // O intervalo é avaliado uma única vez, antes da primeira volta
+        let start = self.execute_expression(&range.start);
+        let end = self.execute_expression(&range.end);
+        let step = match &range.step {
+            Some(step) => self.execute_expression(step),
+            None => Value::Int(1),
+        };
+        let (Value::Int(start), Value::Int(end), Value::Int(step)) = (&start, &end, &step) else {
+            self.runtime_error(format!(
+                "Invalid types for FOR range: {:?}, {:?} and {:?}",
+                start, end, step
+            ));
+            return;
+        };
+        let (start, end, step) = (*start, *end, *step);
+        if step == 0 {
+            self.runtime_error("Passo do for não pode ser zero");
+            return;
+        }
+
+        let old_env = self.env.clone();
+
+        // Com passo negativo o loop conta para baixo
+        let mut current = Some(start);
+        while let Some(i) = current {
+            let in_range = match (step > 0, range.inclusive) {
+                (true, false) => i < end,
+                (true, true) => i <= end,
+                (false, false) => i > end,
+                (false, true) => i >= end,
+            };
+            if !in_range || !self.errors.is_empty() {
+                break;
+            }
+
+            // A variável do loop só existe no corpo; cada volta tem o seu escopo,
+            // para que um closure criado nela guarde o valor daquela volta
+            self.env = RuntimeEnvironment::nest(&old_env);
+            self.env.borrow_mut().variables.insert(
+                var.to_string(),
+                RuntimeVariable {
+                    value: Value::Int(i),
+                    moved: false,
+                },
+            );
+            self.execute_command(body);
+            if self.loop_should_stop(label) {
+                break;
+            }
+
+            // Passar do maior inteiro também encerra o loop
+            current = i.checked_add(step);
+        }
+
+        self.env = old_env;
+    }
+
+    // Trata o break/continue pendente depois de uma volta do corpo. Devolve true se
+    // o loop deve parar, inclusive quando o desvio é para um loop mais externo
+    fn loop_should_stop(&mut self, label: Option<&str>) -> bool {
+        let targets_this_loop =
+            |target: &Option<String>| target.is_none() || target.as_deref() == label;
+
+        match &self.jump {
+            None => false,
+            Some(Jump::Continue(target)) if targets_this_loop(target) => {
+                self.jump = None;
+                false
             }
+            Some(Jump::Break(target)) if targets_this_loop(target) => {
+                self.jump = None;
+                true
+            }
+            Some(_) => true,
         }
     }
 
@@ -155,21 +496,58 @@ impl Executor {
         &mut self,
         condition: &Expression,
         then_cmd: &Command,
-        else_cmd: &Command,
+        else_cmd: &Option<Box<Command>>,
     ) {
         let value = self.execute_expression(condition);
         if let Value::Bool(true) = value {
             self.execute_command(then_cmd);
-        } else {
+        } else if let Some(else_cmd) = else_cmd {
             self.execute_command(else_cmd);
         }
     }
 
+    // Executa o primeiro braço cujo padrão combina com o valor, com as variáveis do
+    // padrão num escopo próprio
+    fn execute_match<T, R>(
+        &mut self,
+        value: &Expression,
+        arms: &[(Pattern, T)],
+        mut execute_body: impl FnMut(&mut Self, &T) -> R,
+    ) -> Option<R> {
+        let value = self.execute_expression(value);
+        let Some((bindings, body)) = arms
+            .iter()
+            .find_map(|(pattern, body)| match_pattern(pattern, &value).map(|b| (b, body)))
+        else {
+            self.runtime_error(format!("Nenhum braço do match combina com {}", value));
+            return None;
+        };
+
+        let old_env = self.env.clone();
+        self.env = RuntimeEnvironment::nest(&old_env);
+        for (name, value) in bindings {
+            self.env.borrow_mut().variables.insert(
+                name,
+                RuntimeVariable {
+                    value,
+                    moved: false,
+                },
+            );
+        }
+        let result = execute_body(self, body);
+        self.env = old_env;
+        Some(result)
+    }
+
     pub fn execute_io(&mut self, io_command: &IOCommand) {
         match io_command {
             IOCommand::Write(expr) => {
+                let errors = self.errors.len();
                 let value = self.execute_expression(expr);
-                println!("{}", value);
+                // Um argumento que falhou não produz saída
+                if self.errors.len() == errors {
+                    println!("{}", value);
+                }
             }
             IOCommand::Read(var) => {
                 let mut input = String::new();
@@ -196,24 +574,56 @@ impl Executor {
     }
 
     fn execute_call_procedure(&mut self, call: &CallProcedure) -> Value {
-        let proc = self.env.borrow().lookup_procedure(&call.id);
-        if proc.is_none() {
-            self.errors
-                .push(format!("Procedimento '{}' não declarado.", call.id));
-            return Value::Unit;
-        }
+        // Um procedimento declarado ou uma variável que guarda um procedimento,
+        // o que estiver no escopo mais próximo
+        let value = self.env.borrow().lookup_value(&call.id);
+        let closure = match value {
+            Some(Value::Closure(closure)) => closure,
+            Some(value) => {
+                return self
+                    .runtime_error(format!("'{}' não é um procedimento: {}", call.id, value));
+            }
+            None if matches!(call.id.as_str(), "push" | "pop") => {
+                return self.execute_builtin(call);
+            }
+            None => {
+                return self.runtime_error(format!("Procedimento '{}' não declarado.", call.id));
+            }
+        };
 
-        let (params, _, body) = proc.unwrap();
         let args: Vec<Value> = call
             .args
             .iter()
             .map(|e| self.execute_expression(e))
             .collect();
+        self.call_closure(&call.id, &closure, args)
+    }
+
+    // Ambiente atual, marcado como guardado por um closure em todos os escopos
+    fn capture_env(&self) -> Rc<RefCell<RuntimeEnvironment>> {
+        let mut current = Some(self.env.clone());
+        while let Some(scope) = current {
+            scope.borrow_mut().captured = true;
+            current = scope.borrow().parent.clone();
+        }
+        self.env.clone()
+    }
+
+    // Uma declaração em um escopo já guardado por um closure vai para um escopo novo,
+    // como no analisador, em que o corpo só enxerga o que foi declarado antes dele
+    fn declaration_scope(&mut self) {
+        if self.env.borrow().captured {
+            self.env = RuntimeEnvironment::nest(&self.env);
+        }
+    }
 
+    // Executa o corpo em um escopo dentro do ambiente do procedimento, com os argumentos
+    // já avaliados no lugar dos parâmetros
+    fn call_closure(&mut self, name: &str, closure: &Closure, args: Vec<Value>) -> Value {
         let old_env = self.env.clone();
-        self.env = RuntimeEnvironment::nest(&old_env);
+        self.env = RuntimeEnvironment::nest(&closure.env);
 
-        for (param, arg_value) in params.iter().zip(args) {
+        for (param, arg_value) in closure.params.iter().zip(args) {
             self.env.borrow_mut().variables.insert(
                 param.identifier.clone(),
                 RuntimeVariable {
@@ -223,44 +633,189 @@ impl Executor {
             );
         }
 
-        self.execute_command(&body);
+        self.execute_command(&closure.body);
 
-        let result = self.get_last_value(&body);
+        // Sem return, o resultado é a expressão no fim do corpo, já avaliada. Um
+        // procedimento sem tipo de retorno descarta essa expressão e devolve unit
+        let tail_value = self.tail_value.take();
+        let result = match (self.jump.take(), tail_value, &closure.return_type) {
+            (Some(Jump::Return(value)), _, _) => value,
+            (_, _, None) => Value::Unit,
+            (_, Some(value), _) => value,
+            (_, None, Some(return_type)) if self.errors.is_empty() => self.runtime_error(format!(
+                "Procedimento '{}' terminou sem devolver um valor do tipo {}",
+                name, return_type
+            )),
+            (_, None, Some(_)) => Value::Unit,
+        };
         self.env = old_env;
         result
     }
 
+    // push e pop alteram a lista no lugar em que ela está guardada
+    fn execute_builtin(&mut self, call: &CallProcedure) -> Value {
+        let Some(list) = call.args.first() else {
+            return self.runtime_error(format!("{} espera uma lista", call.id));
+        };
+        // Sem lugar, o erro já foi registrado por resolve_place
+        let Some((var, steps)) = self.resolve_place(list) else {
+            return Value::Unit;
+        };
+
+        if call.id == "push" {
+            let value = match call.args.get(1) {
+                Some(expr) => self.execute_expression(expr),
+                None => return self.runtime_error("push espera uma lista e um valor"),
+            };
+            self.update_place(&var, &steps, |target| match target {
+                Value::List(values) => {
+                    values.push(value);
+                    Ok(())
+                }
+                other => Err(format!("Invalid type for push: {:?}", other)),
+            });
+            return Value::Unit;
+        }
+
+        self.update_place(&var, &steps, |target| match target {
+            Value::List(values) => values.pop().ok_or_else(|| "pop em lista vazia".to_string()),
+            other => Err(format!("Invalid type for pop: {:?}", other)),
+        })
+        .unwrap_or(Value::Unit)
+    }
+
     pub fn execute_expression(&mut self, expr: &Expression) -> Value {
         match expr {
             Expression::CallProcedure(call) => self.execute_call_procedure(call),
             Expression::ConcreteValue(value) => self.execute_concrete_value(value),
             Expression::Identifier(var) => {
-                let env = self.env.borrow();
-                let variable = env
-                    .lookup_variable(var)
-                    .ok_or_else(|| format!("Variável '{}' não definida", var));
-                match variable {
-                    Ok(variable_info) => {
-                        return variable_info.value;
-                    }
-                    Err(e) => {
-                        panic!("{e}")
-                    }
+                // Um procedimento declarado também pode ser usado como valor
+                let value = self.env.borrow().lookup_value(var);
+                match value {
+                    Some(value) => value,
+                    None => self.runtime_error(format!("Variável '{}' não definida", var)),
                 }
             }
+            Expression::Lambda(params, return_type, body) => Value::Closure(Rc::new(Closure {
+                params: params.clone(),
+                return_type: return_type.clone(),
+                body: (**body).clone(),
+                env: self.capture_env(),
+            })),
+            Expression::Call(callee, args) => match self.execute_expression(callee) {
+                Value::Closure(closure) => {
+                    let args: Vec<Value> =
+                        args.iter().map(|e| self.execute_expression(e)).collect();
+                    self.call_closure(&closure.r#type().to_string(), &closure, args)
+                }
+                value => {
+                    self.runtime_error(format!("Chamada de algo que não é procedimento: {}", value))
+                }
+            },
             Expression::UnaryExp(op, expr) => self.execute_unary_expression(op, expr),
+            Expression::StructLiteral(name, fields) => self.execute_struct_literal(name, fields),
+            Expression::EnumVariant(name, variant, values) => Value::Enum(
+                name.clone(),
+                variant.clone(),
+                values
+                    .iter()
+                    .map(|value| self.execute_expression(value))
+                    .collect(),
+            ),
+            Expression::Match(value, arms) => self
+                .execute_match(value, arms, |executor, body| {
+                    executor.execute_expression(body)
+                })
+                .unwrap_or(Value::Unit),
+            Expression::IfLet(pattern, value, then_expr, else_expr) => {
+                let arms = [
+                    (pattern.clone(), then_expr.as_ref()),
+                    (Pattern::Wildcard, else_expr.as_ref()),
+                ];
+                self.execute_match(value, &arms, |executor, body| {
+                    executor.execute_expression(body)
+                })
+                .unwrap_or(Value::Unit)
+            }
+            Expression::Some(value) => {
+                Value::Option(Some(Box::new(self.execute_expression(value))))
+            }
+            Expression::None => Value::Option(None),
+            Expression::FieldAccess(object, field) => match self.execute_expression(object) {
+                Value::Struct(name, fields) => {
+                    match fields
+                        .into_iter()
+                        .find(|(field_name, _)| field_name == field)
+                    {
+                        Some((_, value)) => value,
+                        None => {
+                            self.runtime_error(format!("Campo '{}' não existe em {}", field, name))
+                        }
+                    }
+                }
+                Value::Tuple(components) => match tuple_component(components, field) {
+                    Ok(value) => value,
+                    Err(message) => self.runtime_error(message),
+                },
+                value => self.runtime_error(format!(
+                    "Invalid type for field access '{}': {:?}",
+                    field, value
+                )),
+            },
+            Expression::ListLiteral(elements) => Value::List(
+                elements
+                    .iter()
+                    .map(|element| self.execute_expression(element))
+                    .collect(),
+            ),
+            Expression::TupleLiteral(components) => Value::Tuple(
+                components
+                    .iter()
+                    .map(|component| self.execute_expression(component))
+                    .collect(),
+            ),
+            Expression::Index(list, index) => {
+                let list = self.execute_expression(list);
+                let Some(index) = self.execute_index(index) else {
+                    return Value::Unit;
+                };
+                match list {
+                    Value::List(values) => match element(&values, index) {
+                        Ok(value) => value.clone(),
+                        Err(message) => self.runtime_error(message),
+                    },
+                    value => self.runtime_error(format!("Invalid type for indexing: {:?}", value)),
+                }
+            }
+            // Só o ramo escolhido é avaliado
+            Expression::IfElse(cond, then_expr, else_expr) => match self.execute_expression(cond) {
+                Value::Bool(true) => self.execute_expression(then_expr),
+                Value::Bool(false) => self.execute_expression(else_expr),
+                value => self.runtime_error(format!("Invalid type for IF condition: {:?}", value)),
+            },
             Expression::BinaryExp(left, op, right) => {
                 self.execute_binary_expression(left, op, right)
             }
+            Expression::Located(span, expr) => {
+                self.at(*span, |executor| executor.execute_expression(expr))
+            }
         }
     }
 
-    fn get_last_value(&mut self, cmd: &Command) -> Value {
-        match cmd {
-            Command::Sequence(_, cmd2) => self.get_last_value(cmd2),
-            Command::Evaluate(expr) => self.execute_expression(expr),
-            _ => Value::Unit,
-        }
+    // Os campos são avaliados na ordem em que foram escritos e guardados na ordem da declaração
+    fn execute_struct_literal(&mut self, name: &str, fields: &[(String, Expression)]) -> Value {
+        let mut values: Vec<(String, Value)> = fields
+            .iter()
+            .map(|(field, expr)| (field.clone(), self.execute_expression(expr)))
+            .collect();
+
+        let Some(declared) = self.env.borrow().lookup_struct(name) else {
+            return self.runtime_error(format!("Struct '{}' não declarada.", name));
+        };
+        let position = |field: &str| declared.iter().position(|d| d.identifier == field);
+        values.sort_by_key(|(field, _)| position(field));
+
+        Value::Struct(name.to_string(), values)
     }
 
     pub fn execute_concrete_value(&mut self, value: &ConcreteValue) -> Value {
@@ -269,6 +824,12 @@ impl Executor {
                 Value::Int(value) => Value::Int(*value),
                 Value::Bool(value) => Value::Bool(*value),
                 Value::Str(value) => Value::Str(value.to_string()),
+                Value::Struct(..)
+                | Value::List(_)
+                | Value::Tuple(_)
+                | Value::Enum(..)
+                | Value::Option(_)
+                | Value::Closure(_) => value.clone(),
                 Value::Unit => Value::Unit,
             },
         }
@@ -280,47 +841,76 @@ impl Executor {
                 Expression::ConcreteValue(value) => match value {
                     ConcreteValue::Value(value) => match value {
                         Value::Bool(value) => Value::Bool(!value),
-                        _ => panic!("Invalid type for NOT operator: {:?}", value),
+                        _ => self
+                            .runtime_error(format!("Invalid type for NOT operator: {:?}", value)),
                     },
                 },
                 _ => {
                     let result = self.execute_expression(expr);
                     match result {
                         Value::Bool(value) => Value::Bool(!value),
-                        _ => panic!("Invalid type for NOT operator in expression: {:?}", result),
+                        _ => self.runtime_error(format!(
+                            "Invalid type for NOT operator in expression: {:?}",
+                            result
+                        )),
                     }
                 }
             },
             UnaryOperator::Neg => match expr {
                 Expression::ConcreteValue(value) => match value {
                     ConcreteValue::Value(value) => match value {
-                        Value::Int(value) => Value::Int(-value),
-                        _ => panic!("Invalid type for NEG operator: {:?}", value),
+                        Value::Int(value) => match value.checked_neg() {
+                            Some(result) => Value::Int(result),
+                            None => {
+                                self.runtime_error(format!("Estouro de inteiro em -({})", value))
+                            }
+                        },
+                        _ => self
+                            .runtime_error(format!("Invalid type for NEG operator: {:?}", value)),
                     },
                 },
                 _ => {
                     let result = self.execute_expression(expr);
                     match result {
-                        Value::Int(value) => Value::Int(-value),
-                        _ => panic!("Invalid type for NEG operator in expression: {:?}", result),
+                        Value::Int(value) => match value.checked_neg() {
+                            Some(result) => Value::Int(result),
+                            None => {
+                                self.runtime_error(format!("Estouro de inteiro em -({})", value))
+                            }
+                        },
+                        _ => self.runtime_error(format!(
+                            "Invalid type for NEG operator in expression: {:?}",
+                            result
+                        )),
                     }
                 }
             },
+            UnaryOperator::BitNot => match self.execute_expression(expr) {
+                Value::Int(value) => Value::Int(!value),
+                result => self.runtime_error(format!(
+                    "Invalid type for BITNOT operator in expression: {:?}",
+                    result
+                )),
+            },
             UnaryOperator::Length => match expr {
                 Expression::ConcreteValue(value) => match value {
                     ConcreteValue::Value(value) => match value {
                         Value::Str(value) => Value::Int(value.len().try_into().unwrap()),
-                        _ => panic!("Invalid type for LENGTH operator: {:?}", value),
+                        _ => self.runtime_error(format!(
+                            "Invalid type for LENGTH operator: {:?}",
+                            value
+                        )),
                     },
                 },
                 _ => {
                     let result = self.execute_expression(expr);
                     match result {
                         Value::Str(value) => Value::Int(value.len().try_into().unwrap()),
-                        _ => panic!(
+                        Value::List(values) => Value::Int(values.len().try_into().unwrap()),
+                        _ => self.runtime_error(format!(
                             "Invalid type for LENGTH operator in expression: {:?}",
                             result
-                        ),
+                        )),
                     }
                 }
             },
@@ -338,19 +928,144 @@ impl Executor {
                 let left = self.execute_expression(left);
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
-                    (Value::Int(left), Value::Int(right)) => Value::Int(left + right),
+                    (Value::Int(left), Value::Int(right)) => match left.checked_add(right) {
+                        Some(result) => Value::Int(result),
+                        None => self
+                            .runtime_error(format!("Estouro de inteiro em {} + {}", left, right)),
+                    },
                     (Value::Str(left), Value::Str(right)) => {
                         Value::Str(format!("{}{}", left, right))
                     }
-                    _ => panic!("Invalid types for ADD operator: {:?} and {:?}", left, right),
+                    _ => self.runtime_error(format!(
+                        "Invalid types for ADD operator: {:?} and {:?}",
+                        left, right
+                    )),
                 }
             }
             BinaryOperator::Sub => {
                 let left = self.execute_expression(left);
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
-                    (Value::Int(left), Value::Int(right)) => Value::Int(left - right),
-                    _ => panic!("Invalid types for SUB operator: {:?} and {:?}", left, right),
+                    (Value::Int(left), Value::Int(right)) => match left.checked_sub(right) {
+                        Some(result) => Value::Int(result),
+                        None => self
+                            .runtime_error(format!("Estouro de inteiro em {} - {}", left, right)),
+                    },
+                    _ => self.runtime_error(format!(
+                        "Invalid types for SUB operator: {:?} and {:?}",
+                        left, right
+                    )),
+                }
+            }
+            BinaryOperator::Mul => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(left), Value::Int(right)) => match left.checked_mul(right) {
+                        Some(result) => Value::Int(result),
+                        None => self
+                            .runtime_error(format!("Estouro de inteiro em {} * {}", left, right)),
+                    },
+                    _ => self.runtime_error(format!(
+                        "Invalid types for MUL operator: {:?} and {:?}",
+                        left, right
+                    )),
+                }
+            }
+            BinaryOperator::Div => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(_), Value::Int(0)) => self.runtime_error("Divisão por zero"),
+                    (Value::Int(left), Value::Int(right)) => match left.checked_div(right) {
+                        Some(result) => Value::Int(result),
+                        None => self
+                            .runtime_error(format!("Estouro de inteiro em {} / {}", left, right)),
+                    },
+                    _ => self.runtime_error(format!(
+                        "Invalid types for DIV operator: {:?} and {:?}",
+                        left, right
+                    )),
+                }
+            }
+            BinaryOperator::Mod => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(_), Value::Int(0)) => {
+                        self.runtime_error("Resto de divisão por zero")
+                    }
+                    (Value::Int(left), Value::Int(right)) => match left.checked_rem(right) {
+                        Some(result) => Value::Int(result),
+                        None => self
+                            .runtime_error(format!("Estouro de inteiro em {} % {}", left, right)),
+                    },
+                    _ => self.runtime_error(format!(
+                        "Invalid types for MOD operator: {:?} and {:?}",
+                        left, right
+                    )),
+                }
+            }
+            BinaryOperator::Pow => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(_), Value::Int(right)) if right < 0 => {
+                        self.runtime_error(format!("Expoente negativo: {}", right))
+                    }
+                    (Value::Int(left), Value::Int(right)) => {
+                        match u32::try_from(right)
+                            .ok()
+                            .and_then(|right| left.checked_pow(right))
+                        {
+                            Some(result) => Value::Int(result),
+                            None => self.runtime_error(format!(
+                                "Estouro de inteiro em {} ** {}",
+                                left, right
+                            )),
+                        }
+                    }
+                    _ => self.runtime_error(format!(
+                        "Invalid types for POW operator: {:?} and {:?}",
+                        left, right
+                    )),
+                }
+            }
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(left), Value::Int(right)) => Value::Int(match op {
+                        BinaryOperator::BitAnd => left & right,
+                        BinaryOperator::BitOr => left | right,
+                        _ => left ^ right,
+                    }),
+                    _ => self.runtime_error(format!(
+                        "Invalid types for {:?} operator: {:?} and {:?}",
+                        op, left, right
+                    )),
+                }
+            }
+            BinaryOperator::Shl | BinaryOperator::Shr => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    // Bits que saem pela esquerda são descartados; ">>" preserva o sinal
+                    (Value::Int(left), Value::Int(right)) => match u32::try_from(right)
+                        .ok()
+                        .filter(|amount| *amount < i64::BITS)
+                    {
+                        Some(amount) if *op == BinaryOperator::Shl => Value::Int(left << amount),
+                        Some(amount) => Value::Int(left >> amount),
+                        None => self.runtime_error(format!(
+                            "Deslocamento inválido: {} (deve estar entre 0 e 63)",
+                            right
+                        )),
+                    },
+                    _ => self.runtime_error(format!(
+                        "Invalid types for {:?} operator: {:?} and {:?}",
+                        op, left, right
+                    )),
                 }
             }
             BinaryOperator::Equal => {
@@ -360,10 +1075,41 @@ impl Executor {
                     (Value::Int(left), Value::Int(right)) => Value::Bool(left == right),
                     (Value::Str(left), Value::Str(right)) => Value::Bool(left == right),
                     (Value::Bool(left), Value::Bool(right)) => Value::Bool(left == right),
-                    _ => panic!(
+                    (left @ Value::Struct(..), right @ Value::Struct(..))
+                    | (left @ Value::List(_), right @ Value::List(_))
+                    | (left @ Value::Tuple(_), right @ Value::Tuple(_))
+                    | (left @ Value::Enum(..), right @ Value::Enum(..))
+                    | (left @ Value::Option(_), right @ Value::Option(_))
+                    // Procedimentos só são iguais se forem o mesmo valor
+                    | (left @ Value::Closure(_), right @ Value::Closure(_)) => {
+                        Value::Bool(left == right)
+                    }
+                    _ => self.runtime_error(format!(
                         "Invalid types for EQUAL operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
+                }
+            }
+            BinaryOperator::NotEqual => {
+                let left = self.execute_expression(left);
+                let right = self.execute_expression(right);
+                match (left.clone(), right.clone()) {
+                    (Value::Int(left), Value::Int(right)) => Value::Bool(left != right),
+                    (Value::Str(left), Value::Str(right)) => Value::Bool(left != right),
+                    (Value::Bool(left), Value::Bool(right)) => Value::Bool(left != right),
+                    (left @ Value::Struct(..), right @ Value::Struct(..))
+                    | (left @ Value::List(_), right @ Value::List(_))
+                    | (left @ Value::Tuple(_), right @ Value::Tuple(_))
+                    | (left @ Value::Enum(..), right @ Value::Enum(..))
+                    | (left @ Value::Option(_), right @ Value::Option(_))
+                    // Procedimentos só são iguais se forem o mesmo valor
+                    | (left @ Value::Closure(_), right @ Value::Closure(_)) => {
+                        Value::Bool(left != right)
+                    }
+                    _ => self.runtime_error(format!(
+                        "Invalid types for NOT_EQUAL operator: {:?} and {:?}",
+                        left, right
+                    )),
                 }
             }
             BinaryOperator::And => {
@@ -371,7 +1117,10 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Bool(left), Value::Bool(right)) => Value::Bool(left && right),
-                    _ => panic!("Invalid types for AND operator: {:?} and {:?}", left, right),
+                    _ => self.runtime_error(format!(
+                        "Invalid types for AND operator: {:?} and {:?}",
+                        left, right
+                    )),
                 }
             }
             BinaryOperator::Or => {
@@ -379,7 +1128,10 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Bool(left), Value::Bool(right)) => Value::Bool(left || right),
-                    _ => panic!("Invalid types for OR operator: {:?} and {:?}", left, right),
+                    _ => self.runtime_error(format!(
+                        "Invalid types for OR operator: {:?} and {:?}",
+                        left, right
+                    )),
                 }
             }
             BinaryOperator::Concat => {
@@ -389,10 +1141,10 @@ impl Executor {
                     (Value::Str(left), Value::Str(right)) => {
                         Value::Str(format!("{}{}", left, right))
                     }
-                    _ => panic!(
+                    _ => self.runtime_error(format!(
                         "Invalid types for CONCAT operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
                 }
             }
             BinaryOperator::Less => {
@@ -400,10 +1152,10 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Int(left), Value::Int(right)) => Value::Bool(left < right),
-                    _ => panic!(
+                    _ => self.runtime_error(format!(
                         "Invalid types for LESS operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
                 }
             }
             BinaryOperator::LessEqual => {
@@ -411,10 +1163,10 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Int(left), Value::Int(right)) => Value::Bool(left <= right),
-                    _ => panic!(
+                    _ => self.runtime_error(format!(
                         "Invalid types for LESS_EQUAL operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
                 }
             }
             BinaryOperator::Greater => {
@@ -422,10 +1174,10 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Int(left), Value::Int(right)) => Value::Bool(left > right),
-                    _ => panic!(
+                    _ => self.runtime_error(format!(
                         "Invalid types for GREATER operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
                 }
             }
             BinaryOperator::GreaterEqual => {
@@ -433,22 +1185,41 @@ impl Executor {
                 let right = self.execute_expression(right);
                 match (left.clone(), right.clone()) {
                     (Value::Int(left), Value::Int(right)) => Value::Bool(left >= right),
-                    _ => panic!(
+                    _ => self.runtime_error(format!(
                         "Invalid types for GREATER_EQUAL operator: {:?} and {:?}",
                         left, right
-                    ),
+                    )),
                 }
             }
         }
     }
 
+    // Registra um erro de execução e devolve Unit para a expressão que falhou
+    fn runtime_error(&mut self, msg: impl Into<String>) -> Value {
+        self.errors.push(Diagnostic::new(msg, self.span));
+        Value::Unit
+    }
+
+    // Executa um nó registrando a sua posição para as mensagens de erro
+    fn at<T>(&mut self, span: Span, execute: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = self.span.replace(span);
+        let result = execute(self);
+        self.span = previous;
+        result
+    }
+
     fn remove_variable(&mut self, var: &str) {
         let mut current = Rc::clone(&self.env);
 
         loop {
             let removed = {
                 let mut env = current.borrow_mut();
-                env.variables.remove(var).is_some()
+                match env.variables.get(var) {
+                    // Os componentes Copy de uma tupla movida continuam disponíveis
+                    Some(variable) if matches!(variable.value, Value::Tuple(_)) => true,
+                    Some(_) => env.variables.remove(var).is_some(),
+                    None => false,
+                }
             };
             if removed {
                 break;
@@ -466,3 +1237,109 @@ impl Executor {
         }
     }
 }
+
+// Variáveis declaradas pelo padrão, com os seus valores, se ele combina com o valor
+fn match_pattern(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => Some(Vec::new()),
+        (Pattern::Binding(name), _) => Some(vec![(name.clone(), value.clone())]),
+        (Pattern::Literal(literal), _) => (literal == value).then(Vec::new),
+        (Pattern::Tuple(patterns), Value::Tuple(values)) => match_all(patterns, values),
+        (
+            Pattern::Variant(name, variant, patterns),
+            Value::Enum(value_name, value_variant, values),
+        ) if name == value_name && variant == value_variant => match_all(patterns, values),
+        (Pattern::Some(pattern), Value::Option(Some(value))) => match_pattern(pattern, value),
+        (Pattern::None, Value::Option(None)) => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+fn match_all(patterns: &[Pattern], values: &[Value]) -> Option<Vec<(String, Value)>> {
+    if patterns.len() != values.len() {
+        return None;
+    }
+    let mut bindings = Vec::new();
+    for (pattern, value) in patterns.iter().zip(values) {
+        bindings.extend(match_pattern(pattern, value)?);
+    }
+    Some(bindings)
+}
+
+// Passo do caminho até um lugar dentro de uma variável
+#[derive(Debug, Clone)]
+enum Step {
+    Field(String),
+    Index(i64),
+}
+
+// Referência ao valor no fim do caminho, ou o erro que impede chegar até ele
+fn place_mut<'a>(target: &'a mut Value, steps: &[Step]) -> Result<&'a mut Value, String> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Ok(target);
+    };
+
+    let inner = match (step, target) {
+        (Step::Field(field), Value::Struct(name, fields)) => fields
+            .iter_mut()
+            .find(|(field_name, _)| field_name == field)
+            .map(|(_, value)| value)
+            .ok_or_else(|| format!("Campo '{}' não existe em {}", field, name))?,
+        (Step::Index(index), Value::List(values)) => {
+            element(values, *index)?;
+            &mut values[*index as usize]
+        }
+        (Step::Field(field), Value::Tuple(components)) => {
+            let len = components.len();
+            field
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| components.get_mut(i))
+                .ok_or_else(|| {
+                    format!(
+                        "Tupla de {} componentes não tem o componente {}",
+                        len, field
+                    )
+                })?
+        }
+        (Step::Field(field), value) => {
+            return Err(format!(
+                "Invalid type for field access '{}': {:?}",
+                field, value
+            ));
+        }
+        (Step::Index(_), value) => {
+            return Err(format!("Invalid type for indexing: {:?}", value));
+        }
+    };
+    place_mut(inner, rest)
+}
+
+// Elemento da lista, com o índice verificado
+fn element(values: &[Value], index: i64) -> Result<&Value, String> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| values.get(i))
+        .ok_or_else(|| {
+            format!(
+                "Índice fora dos limites: {} (tamanho da lista: {})",
+                index,
+                values.len()
+            )
+        })
+}
+
+// Componente da tupla pela posição escrita depois do "."
+fn tuple_component(components: Vec<Value>, field: &str) -> Result<Value, String> {
+    let len = components.len();
+    field
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| components.into_iter().nth(i))
+        .ok_or_else(|| {
+            format!(
+                "Tupla de {} componentes não tem o componente {}",
+                len, field
+            )
+        })
+}
@@ -1,2 +1,3 @@
 pub mod environment;
+#[allow(clippy::module_inception)]
 pub mod executor;
@@ -1,5 +1,7 @@
-pub mod environment;
-pub mod semantic;
 pub mod ast;
-pub mod parsers;
+pub mod diagnostics;
+pub mod environment;
 pub mod executor;
+pub mod parsers;
+pub mod repl;
+pub mod semantic;
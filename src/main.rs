@@ -1,56 +1,134 @@
-fn main() {
-    use plp_2025::executor::executor::Executor;
-    use plp_2025::parsers::program_parser;
-    use plp_2025::semantic::semantic::SemanticAnalyzer;
-
-    let _sample_code_1 = r#"
-    {
-        var c = 5;
-        var d = 10;
-        proc soma(int a, int b) int {
-            a + b
-        };
-        write(c);
-        c := soma(c, d);
-        write(c);
-        call soma(20, 40)
-    }
-    "#;
+use std::io::Read;
+use std::process::ExitCode;
 
-    let code = _sample_code_1;
+use plp_2025::ast::Program;
+use plp_2025::diagnostics::diagnostics::Diagnostic;
+use plp_2025::executor::executor::Executor;
+use plp_2025::parsers::program_parser;
+use plp_2025::repl::repl::Repl;
+use plp_2025::semantic::semantic::SemanticAnalyzer;
 
-    // === PARSING ===
-    let parsed = program_parser::parse_program(code);
-    let program = match parsed {
-        Ok((_, ref program)) => program,
+// Códigos de saída do interpretador
+const EXIT_USAGE: u8 = 1; // Argumentos inválidos ou arquivo ilegível
+const EXIT_SYNTAX: u8 = 2; // Erro de sintaxe
+const EXIT_SEMANTIC: u8 = 3; // Erro semântico
+const EXIT_RUNTIME: u8 = 4; // Erro de execução
+
+const USAGE: &str = "Uso: plp-2025 <comando> <arquivo>
+
+Comandos:
+    run <arquivo>      Verifica e executa o programa
+    check <arquivo>    Apenas verifica a sintaxe e a semântica do programa
+    parse <arquivo>    Mostra a árvore sintática do programa
+    repl               Inicia o modo interativo
+
+Use '-' no lugar do arquivo para ler o programa da entrada padrão.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Run,
+    Check,
+    Parse,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let (mode, path) = match args.as_slice() {
+        [command, path] => match command.as_str() {
+            "run" => (Mode::Run, path),
+            "check" => (Mode::Check, path),
+            "parse" => (Mode::Parse, path),
+            _ => {
+                eprintln!("Comando desconhecido: {}\n\n{}", command, USAGE);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        [repl] if repl == "repl" => {
+            Repl::new().run(std::io::stdin().lock());
+            return ExitCode::SUCCESS;
+        }
+        [help] if help == "-h" || help == "--help" || help == "help" => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let code = match read_source(path) {
+        Ok(code) => code,
         Err(e) => {
-            eprintln!("Erro de sintaxe: {:?}", e);
-            return;
+            eprintln!("Não foi possível ler '{}': {}", path, e);
+            return ExitCode::from(EXIT_USAGE);
         }
     };
 
+    // Nome exibido nas mensagens de erro
+    let name = if path == "-" {
+        "<stdin>"
+    } else {
+        path.as_str()
+    };
+
+    match process(mode, &code, name) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+// Lê o programa de um arquivo ou da entrada padrão quando o caminho é "-"
+fn read_source(path: &str) -> std::io::Result<String> {
+    if path == "-" {
+        let mut code = String::new();
+        std::io::stdin().read_to_string(&mut code)?;
+        Ok(code)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+// Executa as etapas do interpretador até a etapa pedida pelo modo
+fn process(mode: Mode, code: &str, name: &str) -> Result<(), u8> {
+    // === PARSING ===
+    let program = parse(code, name)?;
+    if mode == Mode::Parse {
+        println!("{:#?}", program);
+        return Ok(());
+    }
+
     // === ANÁLISE SEMÂNTICA ===
     let mut analyzer = SemanticAnalyzer::new();
-    match analyzer.check_program(program) {
-        Ok(()) => {
-            // === EXECUÇÃO ===
-            let mut executor = Executor::new();
-            let result = executor.execute_program(program);
-
-            if !executor.errors.is_empty() {
-                eprintln!("Erros de execução encontrados:");
-                for error in executor.errors {
-                    eprintln!("- {}", error);
-                }
-            } else {
-                println!("Resultado: {:?}", result);
-            }
-        }
-        Err(errors) => {
-            eprintln!("Erros semânticos encontrados:");
-            for error in errors {
-                eprintln!("- {}", error);
-            }
-        }
+    if let Err(errors) = analyzer.check_program(&program) {
+        report(&errors, code, name);
+        return Err(EXIT_SEMANTIC);
+    }
+    if mode == Mode::Check {
+        return Ok(());
+    }
+
+    // === EXECUÇÃO ===
+    let mut executor = Executor::new();
+    if let Err(errors) = executor.execute_program(&program) {
+        report(&errors, code, name);
+        return Err(EXIT_RUNTIME);
+    }
+
+    Ok(())
+}
+
+fn parse(code: &str, name: &str) -> Result<Program, u8> {
+    program_parser::parse_program(code).map_err(|errors| {
+        let errors: Vec<Diagnostic> = errors.iter().map(|e| e.to_diagnostic()).collect();
+        report(&errors, code, name);
+        EXIT_SYNTAX
+    })
+}
+
+fn report(errors: &[Diagnostic], code: &str, name: &str) {
+    for error in errors {
+        eprintln!("{}\n", error.render(code, name));
     }
 }
@@ -1,55 +1,244 @@
-use nom::sequence::delimited;
-use nom::{
-    IResult, Parser,
-    branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::{alpha1, multispace0},
-    combinator::{map, recognize},
-    sequence::pair,
-};
+use nom::combinator::cut;
+use nom::combinator::opt;
+use nom::error::context;
+use nom::multi::{many1, separated_list0};
+use nom::sequence::{delimited, preceded, terminated};
+use nom::{Input as _, Parser, combinator::map};
 
-use crate::ast::Type;
+use crate::ast::{Span, Type};
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use crate::parsers::tokens::{Token, TokenKind, Tokens};
 
-//// Parser para espaços em branco
-pub fn ws(input: &str) -> IResult<&str, &str> {
-    multispace0(input)
-}
+// Entrada dos parsers: os tokens produzidos pelo lexer, com as suas posições
+pub type Input<'a> = Tokens<'a>;
 
-// Parser para espaços em branco e parenteses
-pub fn ws_paren(input: &str) -> IResult<&str, &str> {
-    take_while(|c: char| c.is_whitespace() || c == '(' || c == ')').parse(input)
+// Consome o próximo token se ele for do tipo pedido. Em caso de erro informa o que era esperado
+fn next_token<'a>(
+    input: Input<'a>,
+    what: impl Into<String>,
+    predicate: impl Fn(&Token) -> bool,
+) -> PResult<'a, &'a Token<'a>> {
+    match input.first() {
+        Some(token) if predicate(token) => Ok((input.take_from(1), token)),
+        _ => Err(nom::Err::Error(SyntaxError::expected(input, what))),
+    }
 }
 
-pub fn lparen(input: &str) -> IResult<&str, &str> {
-    delimited(ws, tag("("), ws).parse(input)
+pub fn lparen<'a>(input: Input<'a>) -> PResult<'a, &'a Token<'a>> {
+    symbol("(")(input)
 }
 
 // E similar para o fechamento:
-pub fn rparen(input: &str) -> IResult<&str, &str> {
-    delimited(ws, tag(")"), ws).parse(input)
+pub fn rparen<'a>(input: Input<'a>) -> PResult<'a, &'a Token<'a>> {
+    symbol(")")(input)
 }
 
-// Parse para identificador
-pub fn parse_identifier(input: &str) -> IResult<&str, String> {
-    let mut parser = recognize(pair(
-        alt((alpha1, tag("_"))),
-        take_while(|c: char| c.is_alphanumeric() || c == '_'),
-    ));
+// Símbolo fixo, como ";" ou ":=". Em caso de erro informa o símbolo esperado
+pub fn symbol<'a>(text: &'static str) -> impl FnMut(Input<'a>) -> PResult<'a, &'a Token<'a>> {
+    move |input: Input<'a>| next_token(input, format!("'{}'", text), |token| token.is_symbol(text))
+}
+
+// Palavra reservada. O lexer lê palavras inteiras, então "do" não casa com "done"
+pub fn keyword<'a>(word: &'static str) -> impl FnMut(Input<'a>) -> PResult<'a, &'a Token<'a>> {
+    move |input: Input<'a>| next_token(input, format!("'{}'", word), |token| token.is_keyword(word))
+}
+
+// Troca o que era esperado por um nome mais geral (ex: "expression"),
+// quando o parser falha logo no início
+pub fn expect<'a, O, F>(
+    what: &'static str,
+    mut parser: F,
+) -> impl FnMut(Input<'a>) -> PResult<'a, O>
+where
+    F: Parser<Input<'a>, Output = O, Error = SyntaxError>,
+{
+    move |input: Input<'a>| match parser.parse(input) {
+        Err(nom::Err::Error(e)) if e.offset <= input.offset() => {
+            Err(nom::Err::Error(SyntaxError::expected(input, what)))
+        }
+        result => result,
+    }
+}
+
+// Executa o parser e envolve o resultado com o trecho dos tokens consumidos
+pub fn located<'a, T, F>(
+    mut parser: F,
+    wrap: fn(Span, Box<T>) -> T,
+) -> impl FnMut(Input<'a>) -> PResult<'a, T>
+where
+    F: Parser<Input<'a>, Output = T, Error = SyntaxError>,
+{
+    move |input: Input<'a>| {
+        let (rest, node) = parser.parse(input)?;
+        Ok((rest, wrap(skipped_span(input, rest), Box::new(node))))
+    }
+}
+
+// Em caso de erro, pula o trecho inválido até o próximo ';' ou '}' e devolve
+// um nó de erro no lugar, para que o parser continue e encontre outros erros
+pub fn recover<'a, T, F>(
+    mut parser: F,
+    context: Option<&'static str>,
+    error: fn(SyntaxError) -> T,
+    wrap: fn(Span, Box<T>) -> T,
+) -> impl FnMut(Input<'a>) -> PResult<'a, T>
+where
+    F: Parser<Input<'a>, Output = T, Error = SyntaxError>,
+{
+    move |input: Input<'a>| match parser.parse(input) {
+        Err(nom::Err::Error(mut e) | nom::Err::Failure(mut e)) => {
+            e.context.extend(context);
+            let rest = skip_to_sync(input);
+            Ok((rest, wrap(skipped_span(input, rest), Box::new(error(e)))))
+        }
+        result => result,
+    }
+}
+
+// Avança até o próximo ';' ou '}' que não esteja dentro de chaves
+pub fn skip_to_sync(input: Input) -> Input {
+    let mut depth = 0;
+
+    for (i, token) in input.tokens.iter().enumerate() {
+        match token.text {
+            _ if token.kind != TokenKind::Symbol => {}
+            "{" => depth += 1,
+            "}" | ";" if depth == 0 => return input.take_from(i),
+            "}" => depth -= 1,
+            _ => {}
+        }
+    }
+
+    input.take_from(input.input_len())
+}
+
+// Trecho coberto pelos tokens entre duas posições da entrada
+pub fn skipped_span(from: Input, to: Input) -> Span {
+    let consumed = from.input_len() - to.input_len();
+    match from.tokens[..consumed] {
+        [] => Span::new(from.offset(), from.offset()),
+        [ref first, ..] => first.span().merge(from.tokens[consumed - 1].span()),
+    }
+}
 
-    let (input, matched_str) = parser.parse(input)?;
-    Ok((input, matched_str.to_string()))
+// Parse para identificador
+pub fn parse_identifier(input: Input) -> PResult<String> {
+    map(
+        |i| next_token(i, "identifier", |token| token.kind == TokenKind::Identifier),
+        |token| token.text.to_string(),
+    )
+    .parse(input)
 }
 
-pub fn parse_type(input: &str) -> IResult<&str, Type> {
+// Nome depois de um ".": campo de struct (p.x) ou posição em uma tupla (t.0)
+pub fn parse_field_name(input: Input) -> PResult<String> {
     map(
-        alt((tag("int"), tag("string"), tag("bool"), tag("unit"))),
-        |matched_str| match matched_str {
-            "int" => crate::ast::Type::Int,
-            "string" => crate::ast::Type::Str,
-            "bool" => crate::ast::Type::Bool,
-            "unit" => crate::ast::Type::Unit,
-            _ => unreachable!(),
+        |i| {
+            next_token(i, "field name", |token| {
+                token.kind == TokenKind::Identifier
+                    || token.kind == TokenKind::Int
+                        && token.text.bytes().all(|b| b.is_ascii_digit())
+            })
         },
+        |token| token.text.to_string(),
     )
     .parse(input)
 }
+
+// Comentário de documentação: devolve o texto depois do "///"
+pub fn parse_doc_comment(input: Input) -> PResult<String> {
+    let (rest, token) = next_token(input, "doc comment", |token| {
+        matches!(token.kind, TokenKind::Doc(_))
+    })?;
+    match &token.kind {
+        TokenKind::Doc(doc) => Ok((rest, doc.clone())),
+        _ => unreachable!(),
+    }
+}
+
+// Tipo primitivo, nome de uma struct ou enum, lista, tupla ou option
+pub fn parse_type(input: Input) -> PResult<Type> {
+    // Lista: [int], [[string]]
+    if let Ok((rest, _)) = symbol("[")(input) {
+        let (rest, element) =
+            cut(context("in list type", terminated(parse_type, symbol("]")))).parse(rest)?;
+        return Ok((rest, crate::ast::Type::List(Box::new(element))));
+    }
+
+    // Tupla: (int, string), com pelo menos dois componentes
+    if let Ok((rest, _)) = symbol("(")(input) {
+        let (rest, (first, others)) = cut(context(
+            "in tuple type",
+            terminated(
+                (parse_type, many1(preceded(symbol(","), parse_type))),
+                symbol(")"),
+            ),
+        ))
+        .parse(rest)?;
+        let mut components = vec![first];
+        components.extend(others);
+        return Ok((rest, crate::ast::Type::Tuple(components)));
+    }
+
+    // Procedimento: proc(int, int) -> int, ou proc(string) sem valor de retorno
+    if let Ok((rest, _)) = keyword("proc")(input) {
+        let (rest, (params, return_type)) = cut(context(
+            "in procedure type",
+            (
+                delimited(
+                    symbol("("),
+                    separated_list0(symbol(","), parse_type),
+                    symbol(")"),
+                ),
+                opt(preceded(symbol("->"), parse_type)),
+            ),
+        ))
+        .parse(rest)?;
+        let return_type = return_type.unwrap_or(crate::ast::Type::Unit);
+        return Ok((rest, crate::ast::Type::Proc(params, Box::new(return_type))));
+    }
+
+    // Option: option<int>. Em option<option<int>> o lexer junta os dois ">" em um ">>",
+    // então os "option<" seguidos são lidos de uma vez e fechados juntos
+    if let Ok((rest, _)) = keyword("option")(input) {
+        let (mut rest, _) = cut(context("in option type", symbol("<"))).parse(rest)?;
+        let mut depth = 1;
+        while let Ok((after, _)) = (keyword("option"), symbol("<")).parse(rest) {
+            rest = after;
+            depth += 1;
+        }
+
+        let (mut rest, mut inner) = cut(context("in option type", parse_type)).parse(rest)?;
+        let mut open = depth;
+        while open > 0 {
+            if let (true, Ok((after, _))) = (open >= 2, symbol(">>")(rest)) {
+                rest = after;
+                open -= 2;
+            } else {
+                let (after, _) = cut(context("in option type", symbol(">"))).parse(rest)?;
+                rest = after;
+                open -= 1;
+            }
+        }
+
+        for _ in 0..depth {
+            inner = crate::ast::Type::Option(Box::new(inner));
+        }
+        return Ok((rest, inner));
+    }
+
+    let (rest, token) = next_token(input, "type", |token| {
+        token.kind == TokenKind::Identifier
+            || token.kind == TokenKind::Keyword
+                && matches!(token.text, "int" | "string" | "bool" | "unit")
+    })?;
+
+    let parsed_type = match token.text {
+        "int" => crate::ast::Type::Int,
+        "string" => crate::ast::Type::Str,
+        "bool" => crate::ast::Type::Bool,
+        "unit" => crate::ast::Type::Unit,
+        name => crate::ast::Type::Named(name.to_string()),
+    };
+    Ok((rest, parsed_type))
+}
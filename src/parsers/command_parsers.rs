@@ -1,15 +1,19 @@
-use crate::ast::{Command, IOCommand};
-use crate::parsers::basic_parsers::{parse_identifier, ws};
+use crate::ast::{Command, Declaration, Expression, IOCommand, Range};
+use crate::parsers::basic_parsers::{
+    Input, expect, keyword, located, lparen, parse_field_name, parse_identifier, recover, rparen,
+    skip_to_sync, skipped_span, symbol,
+};
 use crate::parsers::declaration_parsers::parse_declaration;
-use crate::parsers::expression_parsers::parse_expression;
-use nom::multi::many0;
+use crate::parsers::expression_parsers::{parse_expression, parse_postfix};
+use crate::parsers::pattern_parsers::{parse_if_let_head, parse_match};
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use nom::error::context;
+use nom::multi::many1;
 use nom::sequence::{pair, terminated};
 use nom::{
-    IResult,
     Parser,
     branch::alt,
-    bytes::complete::tag,
-    combinator::{map, opt, value},
+    combinator::{cut, map, opt, value},
     // multi::separated_list1,
     sequence::{delimited, preceded},
 };
@@ -17,129 +21,429 @@ use nom::{
 use super::expression_parsers::parse_call_expression;
 
 // Parser principal
-pub fn parse_command(input: &str) -> IResult<&str, Command> {
-    let (input, cmd) = alt((
-        parse_call_procedure,
-        parse_assignment,
-        parse_if_else,
-        parse_while_loop,
-        parse_io_command,
-        parse_skip,
-        parse_declaration_block,
-        parse_evaluate,
-    ))
+pub fn parse_command(input: Input) -> PResult<Command> {
+    let (input, cmd) = located(
+        expect(
+            "command",
+            alt((
+                parse_call_procedure,
+                parse_labeled_loop,
+                parse_field_assignment,
+                parse_index_assignment,
+                parse_assignment,
+                parse_if_let,
+                parse_if_else,
+                parse_match_command,
+                parse_while_loop,
+                parse_for_loop,
+                parse_break,
+                parse_continue,
+                parse_return,
+                parse_io_command,
+                parse_skip,
+                parse_declaration_block,
+                parse_evaluate,
+            )),
+        ),
+        Command::Located,
+    )
     .parse(input)?;
 
     parse_sequence(input, cmd)
 }
 
 // Parse command helper with another order of parsers to help the parse_declaration_block to processo complex code
-fn parse_command_with_sequence(input: &str) -> IResult<&str, Command> {
-    let (input, initial_cmd) = alt((
-        parse_call_procedure,
-        parse_if_else,
-        parse_while_loop,
-        parse_assignment,
-        parse_io_command,
-        parse_skip,
-        parse_declaration_block,
-        parse_evaluate,
-    ))
-    .parse(input)?;
+fn parse_block_command(input: Input) -> PResult<Command> {
+    located(
+        expect(
+            "command",
+            alt((
+                parse_call_procedure,
+                parse_if_let,
+                parse_if_else,
+                parse_match_command,
+                parse_while_loop,
+                parse_for_loop,
+                parse_break,
+                parse_continue,
+                parse_return,
+                parse_labeled_loop,
+                parse_field_assignment,
+                parse_index_assignment,
+                parse_assignment,
+                parse_io_command,
+                parse_skip,
+                parse_declaration_block,
+                parse_evaluate,
+            )),
+        ),
+        Command::Located,
+    )
+    .parse(input)
+}
+
+// Sequência de comandos separados por ';' com recuperação de erros: um comando
+// inválido vira Command::Error e a leitura continua depois do próximo ';' ou '}'
+pub fn parse_statements<'a>(
+    context: Option<&'static str>,
+) -> impl FnMut(Input<'a>) -> PResult<'a, Command> {
+    move |input: Input<'a>| {
+        let mut statement = recover(
+            parse_block_command,
+            context,
+            Command::Error,
+            Command::Located,
+        );
+        let (mut input, first) = statement(input)?;
+        let mut commands = vec![first];
 
-    parse_sequence(input, initial_cmd)
+        loop {
+            if let Ok((rest, _)) = symbol(";")(input) {
+                let (rest, cmd) = statement(rest)?;
+                commands.push(cmd);
+                input = rest;
+            } else if input.first().is_none_or(|token| token.is_symbol("}")) {
+                break;
+            } else {
+                // Sobra depois do comando: registra e sincroniza
+                let mut error = SyntaxError::unexpected(input);
+                error.context.extend(context);
+                let sync = skip_to_sync(input);
+                commands.push(Command::Located(
+                    skipped_span(input, sync),
+                    Box::new(Command::Error(error)),
+                ));
+                input = sync;
+            }
+        }
+
+        let last = commands.pop().unwrap();
+        let sequence = commands
+            .into_iter()
+            .rev()
+            .fold(last, |right, left| sequence(left, right));
+        Ok((input, sequence))
+    }
 }
 
 // Helper para sequências
-fn parse_sequence(input: &str, left: Command) -> IResult<&str, Command> {
-    opt(preceded(delimited(ws, tag(";"), ws), parse_command))
+fn parse_sequence(input: Input, left: Command) -> PResult<Command> {
+    opt(preceded(symbol(";"), parse_command))
         .map(|maybe_right| {
             maybe_right
-                .map(|right| Command::Sequence(Box::new(left.clone()), Box::new(right)))
+                .map(|right| sequence(left.clone(), right))
                 .unwrap_or(left.clone())
         })
         .parse(input)
 }
 
+// Junta dois comandos em uma sequência que cobre os dois trechos
+fn sequence(left: Command, right: Command) -> Command {
+    let span = left.span().zip(right.span()).map(|(l, r)| l.merge(r));
+    let sequence = Command::Sequence(Box::new(left), Box::new(right));
+    match span {
+        Some(span) => Command::Located(span, Box::new(sequence)),
+        None => sequence,
+    }
+}
+
 // Atribuição: x := 5
-fn parse_assignment(input: &str) -> IResult<&str, Command> {
+fn parse_assignment(input: Input) -> PResult<Command> {
+    map(
+        (
+            parse_identifier,
+            symbol(":="),
+            cut(context(
+                "in assignment",
+                (opt(keyword("move")), parse_expression),
+            )),
+        ),
+        |(var, _, (is_move, expr))| Command::Assignment(var, expr, is_move.is_some()),
+    )
+    .parse(input)
+}
+
+// Atribuição a um campo: p.x := 5
+fn parse_field_assignment(input: Input) -> PResult<Command> {
     map(
         (
-            delimited(ws, parse_identifier, ws),
-            delimited(ws, tag(":="), ws),
-            delimited(ws, opt(tag("move")), ws),
-            delimited(ws, parse_expression, ws),
+            parse_identifier,
+            many1(preceded(symbol("."), parse_field_name)),
+            symbol(":="),
+            cut(context(
+                "in assignment",
+                (opt(keyword("move")), parse_expression),
+            )),
         ),
-        |(var, _, is_move, expr)| Command::Assignment(var, expr, is_move.is_some()),
+        |(var, path, _, (is_move, expr))| {
+            Command::FieldAssignment(var, path, expr, is_move.is_some())
+        },
     )
     .parse(input)
 }
 
+// Atribuição a um elemento de lista: xs[i] := 5, p.pontos[0] := q
+fn parse_index_assignment(input: Input) -> PResult<Command> {
+    let (rest, target) = parse_postfix(input)?;
+    let Expression::Index(list, index) = target.node() else {
+        return Err(nom::Err::Error(SyntaxError::expected(input, "assignment")));
+    };
+    if !target.is_place() {
+        return Err(nom::Err::Error(SyntaxError::expected(input, "assignment")));
+    }
+
+    map(
+        preceded(
+            symbol(":="),
+            cut(context(
+                "in assignment",
+                (opt(keyword("move")), parse_expression),
+            )),
+        ),
+        |(is_move, expr)| {
+            Command::IndexAssignment((**list).clone(), (**index).clone(), expr, is_move.is_some())
+        },
+    )
+    .parse(rest)
+}
+
 // Bloco de declarações: { var x = 5; var y = 10 }
-fn parse_declaration_block(input: &str) -> IResult<&str, Command> {
+fn parse_declaration_block(input: Input) -> PResult<Command> {
     map(
-        delimited(
-            delimited(ws, tag("{"), ws),
-            pair(
-                // Declarações seguidas de ;
-                many0(terminated(parse_declaration, delimited(ws, tag(";"), ws))),
-                // Sequência de comandos (usando a mesma lógica do parse_command)
-                parse_command_with_sequence,
-            ),
-            delimited(ws, tag("}"), ws),
+        preceded(
+            symbol("{"),
+            cut(terminated(
+                pair(
+                    // Declarações seguidas de ;
+                    parse_block_declarations,
+                    // Sequência de comandos
+                    parse_statements(Some("in block")),
+                ),
+                context("to close block", symbol("}")),
+            )),
         ),
         |(declarations, command)| Command::DeclarationBlock(declarations, Box::new(command)),
     )
     .parse(input)
 }
 
+// Declarações do início do bloco. Uma declaração inválida vira Declaration::Error
+// e a leitura continua depois do próximo ';'
+fn parse_block_declarations(mut input: Input) -> PResult<Vec<Declaration>> {
+    let mut declarations = Vec::new();
+
+    loop {
+        let result = terminated(
+            parse_declaration,
+            cut(context("after declaration", symbol(";"))),
+        )
+        .parse(input);
+
+        match result {
+            Ok((rest, decl)) => {
+                declarations.push(decl);
+                input = rest;
+            }
+            // Não é uma declaração: começam os comandos do bloco
+            Err(nom::Err::Error(_)) => return Ok((input, declarations)),
+            Err(nom::Err::Failure(error)) => {
+                let sync = skip_to_sync(input);
+                declarations.push(Declaration::Located(
+                    skipped_span(input, sync),
+                    Box::new(Declaration::Error(error)),
+                ));
+
+                match symbol(";")(sync) {
+                    Ok((rest, _)) => input = rest,
+                    Err(_) => return Ok((sync, declarations)),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Corpo de while/for e ramo de if: um bloco entre chaves termina no '}',
+// já um comando sem chaves se estende pela sequência que vem depois dele
+fn parse_body(input: Input) -> PResult<Command> {
+    match input.first() {
+        Some(token) if token.is_symbol("{") => {
+            located(parse_declaration_block, Command::Located).parse(input)
+        }
+        _ => parse_command(input),
+    }
+}
+
 // While loop: while cond do cmd
-fn parse_while_loop(input: &str) -> IResult<&str, Command> {
+fn parse_while_loop(input: Input) -> PResult<Command> {
     map(
-        (
-            preceded(
-                terminated(tag("while"), ws),
-                delimited(ws, parse_expression, ws),
-            ),
-            preceded(terminated(tag("do"), ws), parse_command),
+        preceded(
+            keyword("while"),
+            cut((
+                context("in while condition", parse_expression),
+                context("after while condition", keyword("do")),
+                context("in while body", parse_body),
+            )),
         ),
-        |(cond, body)| Command::WhileLoop(cond, Box::new(body)),
+        |(cond, _, body)| Command::WhileLoop(cond, Box::new(body)),
     )
     .parse(input)
 }
 
-// If-else: if cond then cmd1 else cmd2
-fn parse_if_else(input: &str) -> IResult<&str, Command> {
+// For loop: for i in start..end [step n] do cmd, ou start..=end para incluir o fim
+fn parse_for_loop(input: Input) -> PResult<Command> {
+    map(
+        preceded(
+            keyword("for"),
+            cut((
+                context("in for loop", parse_identifier),
+                context("after for variable", keyword("in")),
+                context("in for range", parse_expression),
+                context(
+                    "in for range",
+                    alt((value(true, symbol("..=")), value(false, symbol("..")))),
+                ),
+                context("in for range", parse_expression),
+                opt(preceded(
+                    keyword("step"),
+                    cut(context("in for step", parse_expression)),
+                )),
+                context("after for range", keyword("do")),
+                context("in for body", parse_body),
+            )),
+        ),
+        |(var, _, start, inclusive, end, step, _, body)| {
+            let range = Range {
+                start,
+                end,
+                inclusive,
+                step,
+            };
+            Command::ForLoop(var, range, Box::new(body))
+        },
+    )
+    .parse(input)
+}
+
+// Loop com rótulo: externo: while cond do cmd
+fn parse_labeled_loop(input: Input) -> PResult<Command> {
+    map(
+        pair(
+            terminated(parse_identifier, symbol(":")),
+            cut(context(
+                "after loop label",
+                located(
+                    expect("loop", alt((parse_while_loop, parse_for_loop))),
+                    Command::Located,
+                ),
+            )),
+        ),
+        |(label, cmd)| Command::Labeled(label, Box::new(cmd)),
+    )
+    .parse(input)
+}
+
+// break [rotulo]
+fn parse_break(input: Input) -> PResult<Command> {
+    map(
+        preceded(keyword("break"), opt(parse_identifier)),
+        Command::Break,
+    )
+    .parse(input)
+}
+
+// continue [rotulo]
+fn parse_continue(input: Input) -> PResult<Command> {
+    map(
+        preceded(keyword("continue"), opt(parse_identifier)),
+        Command::Continue,
+    )
+    .parse(input)
+}
+
+// return [expr]
+fn parse_return(input: Input) -> PResult<Command> {
+    map(
+        preceded(
+            keyword("return"),
+            opt(context("in return command", parse_expression)),
+        ),
+        Command::Return,
+    )
+    .parse(input)
+}
+
+// If-else: if cond then cmd1 [else cmd2]. Como o else é um comando,
+// "else if" encadeia outro if sem precisar de regra própria
+fn parse_if_else(input: Input) -> PResult<Command> {
+    map(
+        preceded(
+            keyword("if"),
+            cut((
+                context("in if condition", parse_expression),
+                context("after if condition", keyword("then")),
+                context("in then branch", parse_body),
+                opt(preceded(
+                    keyword("else"),
+                    cut(context("in else branch", parse_body)),
+                )),
+            )),
+        ),
+        |(cond, _, then_cmd, else_cmd)| {
+            Command::IfElse(cond, Box::new(then_cmd), else_cmd.map(Box::new))
+        },
+    )
+    .parse(input)
+}
+
+// if let padrão = valor then cmd [else cmd]
+fn parse_if_let(input: Input) -> PResult<Command> {
     map(
         (
-            preceded(tag("if"), delimited(ws, parse_expression, ws)),
-            preceded(tag("then"), delimited(ws, parse_command, ws)),
-            preceded(tag("else"), delimited(ws, parse_command, ws)),
+            parse_if_let_head,
+            cut((
+                context("in then branch", parse_body),
+                opt(preceded(
+                    keyword("else"),
+                    cut(context("in else branch", parse_body)),
+                )),
+            )),
         ),
-        |(cond, then_cmd, else_cmd)| Command::IfElse(cond, Box::new(then_cmd), Box::new(else_cmd)),
+        |((pattern, value), (then_cmd, else_cmd))| {
+            Command::IfLet(pattern, value, Box::new(then_cmd), else_cmd.map(Box::new))
+        },
     )
     .parse(input)
 }
 
+// Match como comando: cada braço é um comando, como os ramos do if
+fn parse_match_command(input: Input) -> PResult<Command> {
+    map(parse_match(parse_body), |(value, arms)| {
+        Command::Match(value, arms)
+    })
+    .parse(input)
+}
+
 // Comandos de IO: write(expr) ou read(var)
-fn parse_io_command(input: &str) -> IResult<&str, Command> {
+fn parse_io_command(input: Input) -> PResult<Command> {
     alt((
         map(
-            delimited(
-                ws,
-                preceded(
-                    tag("write"),
-                    delimited(tag("("), parse_expression, tag(")")),
-                ),
-                ws,
+            preceded(
+                keyword("write"),
+                cut(context(
+                    "in write command",
+                    delimited(lparen, parse_expression, rparen),
+                )),
             ),
             |expr| Command::IO(IOCommand::Write(Box::new(expr))),
         ),
         map(
-            delimited(
-                ws,
-                preceded(tag("read"), delimited(tag("("), parse_identifier, tag(")"))),
-                ws,
+            preceded(
+                keyword("read"),
+                cut(context(
+                    "in read command",
+                    delimited(lparen, parse_identifier, rparen),
+                )),
             ),
             |var| Command::IO(IOCommand::Read(var)),
         ),
@@ -148,17 +452,18 @@ fn parse_io_command(input: &str) -> IResult<&str, Command> {
 }
 
 // Comando skip
-fn parse_skip(input: &str) -> IResult<&str, Command> {
-    value(Command::Skip, tag("skip")).parse(input)
+fn parse_skip(input: Input) -> PResult<Command> {
+    value(Command::Skip, keyword("skip")).parse(input)
 }
 
-fn parse_evaluate(input: &str) -> IResult<&str, Command> {
-    map(parse_expression, |expr| Command::Evaluate(expr)).parse(input)
+fn parse_evaluate(input: Input) -> PResult<Command> {
+    map(parse_expression, Command::Evaluate).parse(input)
 }
 
-fn parse_call_procedure(input: &str) -> IResult<&str, Command> {
-    let (input, _) = delimited(ws, tag("call"), ws).parse(input)?;
-    let (input, call_expr) = delimited(ws, parse_call_expression, ws).parse(input)?;
+fn parse_call_procedure(input: Input) -> PResult<Command> {
+    let (input, _) = keyword("call")(input)?;
+    let (input, call_expr) =
+        cut(context("in procedure call", parse_call_expression)).parse(input)?;
 
     Ok((input, Command::Evaluate(call_expr)))
 }
@@ -1,42 +1,103 @@
 use crate::ast::{ConcreteValue, Value};
+use crate::parsers::basic_parsers::{Input, keyword, symbol};
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use crate::parsers::tokens::TokenKind;
 use nom::{
-    IResult, Parser,
+    Input as _, Parser,
     branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::digit1,
-    combinator::{map, value},
-    sequence::delimited,
+    combinator::{opt, value},
 };
 
-// Parse Int
-pub fn parse_int(input: &str) -> IResult<&str, ConcreteValue> {
-    let (input, converted_int_str) = digit1(input)?; // Reconhece os Números
+// Parse Int. Um '-' colado ao número faz parte do literal, para que
+// -9223372036854775808 seja aceito sem passar por UnaryOperator::Neg. Antes de
+// "**" o '-' fica com o operador, já que "-2 ** 2" é "-(2 ** 2)"
+pub fn parse_int(input: Input) -> PResult<ConcreteValue> {
+    let (after_sign, minus) = opt(symbol("-")).parse(input)?;
+    let token = match after_sign.first() {
+        Some(token)
+            if token.kind == TokenKind::Int
+                && minus.is_none_or(|minus| {
+                    minus.span().end == token.position.offset
+                        && symbol("**")(after_sign.take_from(1)).is_err()
+                }) =>
+        {
+            token
+        }
+        _ => return Err(nom::Err::Error(SyntaxError::expected(input, "integer"))),
+    };
 
-    let num = converted_int_str.parse::<i64>().map_err(|_| {
-        // Converte para i64
-        nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Digit))
-    })?;
+    let text = match minus {
+        Some(_) => format!("-{}", token.text),
+        None => token.text.to_string(),
+    };
+    let invalid = |reason| nom::Err::Failure(SyntaxError::invalid(input, &text, reason));
 
-    Ok((input, ConcreteValue::Value(Value::Int(num))))
+    let (radix, digits, missing, invalid_digit) = match token.text.get(..2) {
+        Some("0x") => (
+            16,
+            &token.text[2..],
+            "missing digits in hexadecimal literal",
+            "invalid digit in hexadecimal literal",
+        ),
+        Some("0b") => (
+            2,
+            &token.text[2..],
+            "missing digits in binary literal",
+            "invalid digit in binary literal",
+        ),
+        Some("0o") => (
+            8,
+            &token.text[2..],
+            "missing digits in octal literal",
+            "invalid digit in octal literal",
+        ),
+        _ => (10, token.text, "", "invalid digit in integer literal"),
+    };
+
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err(invalid(missing));
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(invalid(invalid_digit));
+    }
+
+    // Converte para i64
+    let num = u64::from_str_radix(&digits, radix)
+        .ok()
+        .and_then(|magnitude| {
+            let magnitude = i128::from(magnitude);
+            i64::try_from(if minus.is_some() {
+                -magnitude
+            } else {
+                magnitude
+            })
+            .ok()
+        })
+        .ok_or_else(|| invalid("integer literal out of range for int"))?;
+
+    Ok((
+        after_sign.take_from(1),
+        ConcreteValue::Value(Value::Int(num)),
+    ))
 }
 
-// Parse String
-pub fn parse_string(input: &str) -> IResult<&str, ConcreteValue> {
-    delimited(
-        tag("\""),
-        map(take_while(|c: char| c != '"'), |s: &str| {
-            ConcreteValue::Value(Value::Str(s.to_string()))
-        }),
-        tag("\""),
-    )
-    .parse(input)
+// Parse String. As aspas já foram retiradas pelo lexer
+pub fn parse_string(input: Input) -> PResult<ConcreteValue> {
+    match input.first().map(|token| &token.kind) {
+        Some(TokenKind::Str(text)) => Ok((
+            input.take_from(1),
+            ConcreteValue::Value(Value::Str(text.clone())),
+        )),
+        _ => Err(nom::Err::Error(SyntaxError::expected(input, "string"))),
+    }
 }
 
 // Parse Bool
-pub fn parse_bool(input: &str) -> IResult<&str, ConcreteValue> {
+pub fn parse_bool(input: Input) -> PResult<ConcreteValue> {
     alt((
-        value(ConcreteValue::Value(Value::Bool(true)), tag("true")),
-        value(ConcreteValue::Value(Value::Bool(false)), tag("false")),
+        value(ConcreteValue::Value(Value::Bool(true)), keyword("true")),
+        value(ConcreteValue::Value(Value::Bool(false)), keyword("false")),
     ))
     .parse(input)
 }
@@ -1,21 +1,37 @@
-use crate::ast::Declaration;
-use crate::ast::ProcedureParameter;
-use crate::parsers::basic_parsers::{parse_identifier, parse_type, ws};
-use crate::parsers::command_parsers::parse_command;
+use crate::ast::{Declaration, Expression};
+use crate::ast::{EnumVariant, ProcedureParameter, StructField};
+use crate::parsers::basic_parsers::{
+    Input, keyword, located, parse_doc_comment, parse_identifier, parse_type, symbol,
+};
+use crate::parsers::command_parsers::parse_statements;
 use crate::parsers::expression_parsers::parse_expression;
+use crate::parsers::syntax_error::PResult;
 use nom::Parser;
 use nom::branch::alt;
-use nom::combinator::opt;
-use nom::sequence::preceded;
+use nom::combinator::{cut, not, opt};
+use nom::error::context;
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, terminated};
 use nom::{
-    IResult, bytes::complete::tag, combinator::map, multi::separated_list1, sequence::delimited,
+    combinator::map,
+    multi::{separated_list0, separated_list1},
+    sequence::delimited,
 };
+
 // Parser principal para declarações
-pub fn parse_declaration(input: &str) -> IResult<&str, Declaration> {
+pub fn parse_declaration(input: Input) -> PResult<Declaration> {
     // println!("Input 1 {}", input);
     let (input, declarations) = separated_list1(
-        delimited(ws, tag(","), ws),
-        alt((parse_single_declaration, parse_procedure_declaration)),
+        symbol(","),
+        located(
+            alt((
+                parse_single_declaration,
+                parse_procedure_declaration,
+                parse_struct_declaration,
+                parse_enum_declaration,
+            )),
+            Declaration::Located,
+        ),
     )
     .parse(input)?;
 
@@ -23,38 +39,76 @@ pub fn parse_declaration(input: &str) -> IResult<&str, Declaration> {
 
     let combined = declarations
         .into_iter()
-        .reduce(|acc, decl| Declaration::Compound(Box::new(acc), Box::new(decl)))
+        .reduce(|acc, decl| {
+            let span = acc.span().zip(decl.span()).map(|(a, d)| a.merge(d));
+            let compound = Declaration::Compound(Box::new(acc), Box::new(decl));
+            match span {
+                Some(span) => Declaration::Located(span, Box::new(compound)),
+                None => compound,
+            }
+        })
         .expect("Pelo menos uma declaração");
 
     Ok((input, combined))
 }
 
-// Parser para uma única declaração
-fn parse_single_declaration(input: &str) -> IResult<&str, Declaration> {
+// Valor inicial de uma declaração, que pode ser movido: = [move] expr
+fn parse_initializer(input: Input) -> PResult<(Expression, bool)> {
+    preceded(
+        symbol("="),
+        alt((
+            map(preceded(keyword("move"), parse_expression), |expr| {
+                (expr, true)
+            }),
+            map(parse_expression, |expr| (expr, false)),
+        )),
+    )
+    .parse(input)
+}
+
+// Desestruturação de uma tupla: var (q, r) = divmod(a, b)
+fn parse_destructuring(input: Input) -> PResult<Declaration> {
     map(
         (
-            tag("var"),
-            ws,
-            parse_identifier,
-            ws,
-            tag("="),
-            ws,
-            alt((
-                map((tag("move"), ws, parse_expression), |(_, _, expr)| {
-                    (expr, true)
-                }),
-                map(parse_expression, |expr| (expr, false)),
-            )),
+            delimited(
+                symbol("("),
+                separated_list1(symbol(","), parse_identifier),
+                symbol(")"),
+            ),
+            parse_initializer,
         ),
-        |(_, _, name, _, _, _, (expr, is_move))| Declaration::Variable(name, expr, is_move),
+        |(names, (expr, is_move))| Declaration::Destructuring(names, expr, is_move),
     )
     .parse(input)
 }
 
-pub fn parse_procedure_parameter(input: &str) -> IResult<&str, ProcedureParameter> {
+// Parser para uma única declaração
+fn parse_single_declaration(input: Input) -> PResult<Declaration> {
+    let (input, _) = keyword("var")(input)?;
+
+    // "var (" só pode ser uma desestruturação
+    if symbol("(")(input).is_ok() {
+        return cut(context("in variable declaration", parse_destructuring)).parse(input);
+    }
+
+    cut(context(
+        "in variable declaration",
+        map(
+            (
+                parse_identifier,
+                opt(preceded(symbol(":"), parse_type)),
+                parse_initializer,
+            ),
+            |(name, r#type, (expr, is_move))| Declaration::Variable(name, r#type, expr, is_move),
+        ),
+    ))
+    .parse(input)
+}
+
+pub fn parse_procedure_parameter(input: Input) -> PResult<ProcedureParameter> {
     map(
-        (parse_type, tag(" "), parse_identifier),
-        |(type_name, _, identifier_name)| ProcedureParameter {
+        (parse_type, parse_identifier),
+        |(type_name, identifier_name)| ProcedureParameter {
             identifier: identifier_name.to_string(),
             r#type: type_name,
         },
@@ -62,41 +116,138 @@ pub fn parse_procedure_parameter(input: &str) -> IResult<&str, ProcedureParamete
     .parse(input)
 }
 
-pub fn parse_procedure_parameters(input: &str) -> IResult<&str, Vec<ProcedureParameter>> {
-    separated_list1(delimited(ws, tag(","), ws), parse_procedure_parameter).parse(input)
+pub fn parse_procedure_parameters(input: Input) -> PResult<Vec<ProcedureParameter>> {
+    // Depois de uma vírgula é obrigatório um novo parâmetro
+    map(
+        pair(
+            parse_procedure_parameter,
+            many0(preceded(symbol(","), cut(parse_procedure_parameter))),
+        ),
+        |(first, rest)| std::iter::once(first).chain(rest).collect(),
+    )
+    .parse(input)
 }
 
-pub fn parse_procedure_declaration(input: &str) -> IResult<&str, Declaration> {
+pub fn parse_procedure_declaration(input: Input) -> PResult<Declaration> {
     // println!("{}", input);
     map(
-        (
-            preceded(ws, tag("proc")),
-            preceded(ws, parse_identifier), // Nome
-            delimited(
-                // Parâmetros
-                tag("("),
-                opt(parse_procedure_parameters),
-                tag(")"),
-            ),
-            delimited(ws, opt(parse_type), ws),
-            delimited(
-                delimited(ws, tag("{"), ws),
-                parse_command,
-                delimited(ws, tag("}"), ws),
+        pair(
+            // Comentários de documentação antes do "proc". Um "(" logo depois é um
+            // procedimento anônimo, lido como expressão
+            terminated(
+                many0(parse_doc_comment),
+                pair(keyword("proc"), not(symbol("("))),
             ),
+            cut((
+                context("in procedure name", parse_identifier), // Nome
+                context(
+                    "in procedure parameter list",
+                    delimited(
+                        // Parâmetros
+                        symbol("("),
+                        opt(parse_procedure_parameters),
+                        symbol(")"),
+                    ),
+                ),
+                opt(parse_type),
+                context(
+                    "in procedure body",
+                    delimited(
+                        symbol("{"),
+                        parse_statements(Some("in procedure body")),
+                        symbol("}"),
+                    ),
+                ),
+            )),
         ),
-        |(_, name, parameters, return_type, body)| {
-            Declaration::Procedure(
+        |(docs, (name, parameters, return_type, body))| {
+            let procedure = Declaration::Procedure(
                 name,
                 parameters.unwrap_or_default(),
                 return_type,
                 Box::new(body),
-            )
+            );
+            if docs.is_empty() {
+                return procedure;
+            }
+
+            // Uma linha por comentário, sem o espaço logo depois do "///"
+            let doc = docs
+                .iter()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+                .collect::<Vec<_>>()
+                .join("\n");
+            Declaration::Documented(doc, Box::new(procedure))
         },
     )
     .parse(input)
 }
 
+// Struct: struct Ponto { int x, int y }
+pub fn parse_struct_declaration(input: Input) -> PResult<Declaration> {
+    map(
+        preceded(
+            keyword("struct"),
+            cut(context(
+                "in struct declaration",
+                (
+                    parse_identifier,
+                    delimited(
+                        symbol("{"),
+                        separated_list0(
+                            symbol(","),
+                            map((parse_type, parse_identifier), |(r#type, identifier)| {
+                                StructField { identifier, r#type }
+                            }),
+                        ),
+                        symbol("}"),
+                    ),
+                ),
+            )),
+        ),
+        |(name, fields)| Declaration::Struct(name, fields),
+    )
+    .parse(input)
+}
+
+// Enum: enum Forma { Circulo(int), Retangulo(int, int), Vazio }
+pub fn parse_enum_declaration(input: Input) -> PResult<Declaration> {
+    map(
+        preceded(
+            keyword("enum"),
+            cut(context(
+                "in enum declaration",
+                (
+                    parse_identifier,
+                    delimited(
+                        symbol("{"),
+                        separated_list0(
+                            symbol(","),
+                            map(
+                                (
+                                    parse_identifier,
+                                    opt(delimited(
+                                        symbol("("),
+                                        separated_list0(symbol(","), parse_type),
+                                        symbol(")"),
+                                    )),
+                                ),
+                                |(identifier, payload)| EnumVariant {
+                                    identifier,
+                                    payload: payload.unwrap_or_default(),
+                                },
+                            ),
+                        ),
+                        symbol("}"),
+                    ),
+                ),
+            )),
+        ),
+        |(name, variants)| Declaration::Enum(name, variants),
+    )
+    .parse(input)
+}
+
 // pub fn parse_procedure_declaration(input: &str) -> IResult<&str, Declaration> {
 //     map(
 //         (
@@ -1,63 +1,105 @@
 use crate::ast::{CallProcedure, Expression};
-use crate::parsers::basic_parsers::{lparen, parse_identifier, rparen, ws};
+use crate::parsers::basic_parsers::{
+    Input, expect, keyword, located, lparen, parse_field_name, parse_identifier, parse_type,
+    rparen, skipped_span, symbol,
+};
+use crate::parsers::command_parsers::parse_statements;
 use crate::parsers::concret_value_parsers::{parse_bool, parse_int, parse_string};
-use crate::parsers::operators_parsers::{parse_binary_operator, parse_unary_operator, precedence};
-use nom::multi::separated_list0;
-use nom::sequence::preceded;
+use crate::parsers::declaration_parsers::parse_procedure_parameters;
+use crate::parsers::operators_parsers::{
+    Associativity, parse_binary_operator, parse_unary_operator, precedence, unary_precedence,
+};
+use crate::parsers::pattern_parsers::{parse_if_let_head, parse_match};
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use nom::error::{ContextError, context};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{preceded, terminated};
 use nom::{
-    IResult, Parser, branch::alt, bytes::complete::tag, combinator::map, sequence::delimited,
+    Parser,
+    branch::alt,
+    combinator::{cut, map, opt, peek},
+    sequence::delimited,
 };
 
-pub fn parse_concrete_value(input: &str) -> IResult<&str, Expression> {
-    delimited(
-        ws,
-        alt((
-            map(parse_int, Expression::ConcreteValue),
-            map(parse_bool, Expression::ConcreteValue),
-            map(parse_string, Expression::ConcreteValue),
-        )),
-        ws,
-    )
+pub fn parse_concrete_value(input: Input) -> PResult<Expression> {
+    alt((
+        map(parse_int, Expression::ConcreteValue),
+        map(parse_bool, Expression::ConcreteValue),
+        map(parse_string, Expression::ConcreteValue),
+    ))
     .parse(input)
 }
 
-// Depois implemento
-pub fn parse_parenthesized(input: &str) -> IResult<&str, Expression> {
-    delimited(tag("("), delimited(ws, parse_expression, ws), tag(")")).parse(input)
+// Expressão entre parênteses, ou tupla quando há mais de um componente: (1, "a")
+pub fn parse_parenthesized(input: Input) -> PResult<Expression> {
+    map(
+        preceded(
+            symbol("("),
+            cut(terminated(
+                separated_list1(symbol(","), parse_expression),
+                context("to close parenthesized expression", symbol(")")),
+            )),
+        ),
+        |mut components| match components.len() {
+            1 => components.remove(0),
+            _ => Expression::TupleLiteral(components),
+        },
+    )
+    .parse(input)
 }
 
-pub fn parse_expression_atomic(input: &str) -> IResult<&str, Expression> {
-    delimited(
-        ws,
-        alt((
-            parse_concrete_value,
-            parse_parenthesized,
-            parse_call_expression,
-            map(parse_identifier, Expression::Identifier),
-        )),
-        ws,
+pub fn parse_expression_atomic(input: Input) -> PResult<Expression> {
+    located(
+        expect(
+            "expression",
+            alt((
+                parse_concrete_value,
+                parse_parenthesized,
+                parse_option,
+                parse_lambda,
+                parse_enum_variant,
+                parse_call_expression,
+                parse_struct_literal,
+                parse_list_literal,
+                map(parse_identifier, Expression::Identifier),
+            )),
+        ),
+        Expression::Located,
     )
     .parse(input)
 }
 
 // Parser principal
-pub fn parse_expression(input: &str) -> IResult<&str, Expression> {
+pub fn parse_expression(input: Input) -> PResult<Expression> {
     // let (input, _) = parse_parenthesized(input)?;
     parse_expr_bp(input, 0)
 }
 
 // Função recursiva para pegar blocos diferentes de expressoes. Ex: 5 + 10 = 15 = ((5 + 10) = 15)
-pub fn parse_expr_bp(input: &str, min_prec: u8) -> IResult<&str, Expression> {
+pub fn parse_expr_bp(input: Input, min_prec: u8) -> PResult<Expression> {
     let (mut input, mut lhs) = parse_primary(input)?;
 
     while let Ok((remaining, op)) = parse_binary_operator(input) {
-        let prec = precedence(&op);
+        let (prec, associativity) = precedence(&op);
         if prec < min_prec {
             break;
         }
 
-        let (new_input, rhs) = parse_expr_bp(remaining, prec + 1)?;
+        // À esquerda, o lado direito só aceita operadores mais fortes;
+        // à direita, aceita também o mesmo operador
+        let next_min = match associativity {
+            Associativity::Left => prec + 1,
+            Associativity::Right => prec,
+        };
+        let (new_input, rhs) = cut(context("after binary operator", |i| {
+            parse_expr_bp(i, next_min)
+        }))
+        .parse(remaining)?;
+        let span = lhs.span().zip(rhs.span()).map(|(l, r)| l.merge(r));
         lhs = Expression::BinaryExp(op, Box::new(lhs), Box::new(rhs));
+        if let Some(span) = span {
+            lhs = Expression::Located(span, Box::new(lhs));
+        }
         input = new_input;
     }
 
@@ -65,37 +107,280 @@ pub fn parse_expr_bp(input: &str, min_prec: u8) -> IResult<&str, Expression> {
 }
 
 // Primary Expressions
-pub fn parse_primary(input: &str) -> IResult<&str, Expression> {
+pub fn parse_primary(input: Input) -> PResult<Expression> {
+    expect(
+        "expression",
+        alt((
+            located(parse_length_expression, Expression::Located),
+            located(parse_if_let_expression, Expression::Located),
+            located(parse_if_expression, Expression::Located),
+            located(parse_match_expression, Expression::Located),
+            // Antes dos unários, para que "-5" seja lido como um literal negativo
+            parse_postfix,
+            located(parse_unary_expression, Expression::Located),
+        )),
+    )
+    .parse(input)
+}
+
+// Acesso a campos e elementos e chamadas depois de uma expressão atômica:
+// p.x, conta.dono.nome, xs[i], fs[0](1)
+pub fn parse_postfix(input: Input) -> PResult<Expression> {
+    let (mut input, mut expr) = parse_expression_atomic(input)?;
+
+    loop {
+        let (rest, postfix) = if let Ok((after_dot, _)) = symbol(".")(input) {
+            let (rest, field) =
+                cut(context("in field access", parse_field_name)).parse(after_dot)?;
+            (rest, Expression::FieldAccess(Box::new(expr.clone()), field))
+        } else if let Ok((after_bracket, _)) = symbol("[")(input) {
+            let (rest, index) = cut(context(
+                "in list index",
+                terminated(parse_expression, symbol("]")),
+            ))
+            .parse(after_bracket)?;
+            (
+                rest,
+                Expression::Index(Box::new(expr.clone()), Box::new(index)),
+            )
+        } else if let Ok((after_paren, _)) = symbol("(")(input) {
+            let (rest, args) = cut(context(
+                "in procedure call arguments",
+                terminated(separated_list0(symbol(","), parse_expression), symbol(")")),
+            ))
+            .parse(after_paren)?;
+            (rest, Expression::Call(Box::new(expr.clone()), args))
+        } else {
+            break;
+        };
+
+        let span = expr
+            .span()
+            .map(|span| span.merge(skipped_span(input, rest)));
+        expr = postfix;
+        if let Some(span) = span {
+            expr = Expression::Located(span, Box::new(expr));
+        }
+        input = rest;
+    }
+
+    Ok((input, expr))
+}
+
+// Variante de enum: Forma::Circulo(3) ou Forma::Vazio
+fn parse_enum_variant(input: Input) -> PResult<Expression> {
+    map(
+        (
+            parse_identifier,
+            symbol("::"),
+            cut(context(
+                "in enum variant",
+                (
+                    parse_identifier,
+                    opt(delimited(
+                        symbol("("),
+                        separated_list0(symbol(","), parse_expression),
+                        symbol(")"),
+                    )),
+                ),
+            )),
+        ),
+        |(name, _, (variant, values))| {
+            Expression::EnumVariant(name, variant, values.unwrap_or_default())
+        },
+    )
+    .parse(input)
+}
+
+// Match como expressão: cada braço é uma expressão
+fn parse_match_expression(input: Input) -> PResult<Expression> {
+    map(parse_match(parse_expression), |(value, arms)| {
+        Expression::Match(Box::new(value), arms)
+    })
+    .parse(input)
+}
+
+// Literal de lista: [1, 2, 3]
+fn parse_list_literal(input: Input) -> PResult<Expression> {
+    map(
+        preceded(
+            symbol("["),
+            cut(context(
+                "in list literal",
+                terminated(separated_list0(symbol(","), parse_expression), symbol("]")),
+            )),
+        ),
+        Expression::ListLiteral,
+    )
+    .parse(input)
+}
+
+// Literal de struct: Ponto { x: 1, y: 2 }. Só é um literal se depois da chave vier
+// "campo:" ou "}", então em "match p { ... }" a chave é o início dos braços
+fn parse_struct_literal(input: Input) -> PResult<Expression> {
+    let struct_start = (
+        parse_identifier,
+        symbol("{"),
+        alt((
+            map(symbol("}"), |_| ()),
+            map((parse_identifier, symbol(":")), |_| ()),
+        )),
+    );
+    peek(struct_start).parse(input)?;
+
+    map(
+        (
+            parse_identifier,
+            symbol("{"),
+            cut(context(
+                "in struct literal",
+                terminated(
+                    separated_list0(
+                        symbol(","),
+                        map(
+                            (parse_identifier, symbol(":"), parse_expression),
+                            |(field, _, value)| (field, value),
+                        ),
+                    ),
+                    symbol("}"),
+                ),
+            )),
+        ),
+        |(name, _, fields)| Expression::StructLiteral(name, fields),
+    )
+    .parse(input)
+}
+
+fn parse_length_expression(input: Input) -> PResult<Expression> {
+    map(
+        preceded(
+            keyword("length"),
+            delimited(lparen, parse_expression, rparen),
+        ),
+        |expr| Expression::UnaryExp(crate::ast::UnaryOperator::Length, Box::new(expr)),
+    )
+    .parse(input)
+}
+
+// Procedimento anônimo: proc(int x) int { x * 2 }, como uma declaração sem nome
+fn parse_lambda(input: Input) -> PResult<Expression> {
+    map(
+        preceded(
+            keyword("proc"),
+            cut((
+                context(
+                    "in lambda parameter list",
+                    delimited(symbol("("), opt(parse_procedure_parameters), symbol(")")),
+                ),
+                opt(parse_type),
+                context(
+                    "in lambda body",
+                    delimited(
+                        symbol("{"),
+                        parse_statements(Some("in lambda body")),
+                        symbol("}"),
+                    ),
+                ),
+            )),
+        ),
+        |(params, return_type, body)| {
+            Expression::Lambda(params.unwrap_or_default(), return_type, Box::new(body))
+        },
+    )
+    .parse(input)
+}
+
+// some(expr) ou none
+fn parse_option(input: Input) -> PResult<Expression> {
     alt((
-        parse_length_expression,
-        delimited(ws, parse_unary_expression, ws),
-        parse_expression_atomic,
+        map(
+            preceded(
+                keyword("some"),
+                cut(context(
+                    "in some value",
+                    delimited(lparen, parse_expression, rparen),
+                )),
+            ),
+            |value| Expression::Some(Box::new(value)),
+        ),
+        map(keyword("none"), |_| Expression::None),
     ))
     .parse(input)
 }
 
-fn parse_length_expression(input: &str) -> IResult<&str, Expression> {
+// If let como expressão: if let padrão = valor then a else b, com o else obrigatório
+fn parse_if_let_expression(input: Input) -> PResult<Expression> {
     map(
-        preceded(tag("length"), delimited(lparen, parse_expression, rparen)),
-        |expr| Expression::UnaryExp(crate::ast::UnaryOperator::Length, Box::new(expr)),
+        (
+            parse_if_let_head,
+            cut((
+                context("in then branch", parse_expression),
+                context("after then branch", keyword("else")),
+                context("in else branch", parse_expression),
+            )),
+        ),
+        |((pattern, value), (then_expr, _, else_expr))| {
+            Expression::IfLet(
+                pattern,
+                Box::new(value),
+                Box::new(then_expr),
+                Box::new(else_expr),
+            )
+        },
+    )
+    .parse(input)
+}
+
+// If como expressão: if cond then a else b. O else é obrigatório, já que a
+// expressão sempre precisa de um valor
+fn parse_if_expression(input: Input) -> PResult<Expression> {
+    map(
+        preceded(
+            keyword("if"),
+            cut((
+                context("in if condition", parse_expression),
+                context("after if condition", keyword("then")),
+                context("in then branch", parse_expression),
+                context("after then branch", keyword("else")),
+                context("in else branch", parse_expression),
+            )),
+        ),
+        |(cond, _, then_expr, _, else_expr)| {
+            Expression::IfElse(Box::new(cond), Box::new(then_expr), Box::new(else_expr))
+        },
     )
     .parse(input)
 }
 
 // Parser unário
-pub fn parse_unary_expression(input: &str) -> IResult<&str, Expression> {
+pub fn parse_unary_expression(input: Input) -> PResult<Expression> {
     let (input, op) = parse_unary_operator(input)?;
-    let (input, exp) = parse_expr_bp(input, 100)?; // Alta precedência
+    let prec = unary_precedence(&op);
+    // O contexto só vale quando falta o operando; um erro dentro dele mantém o seu
+    let (input, exp) = match parse_expr_bp(input, prec) {
+        Ok(result) => result,
+        Err(nom::Err::Error(error) | nom::Err::Failure(error)) => {
+            let error = if error.offset == input.offset() {
+                SyntaxError::add_context(input, "after unary operator", error)
+            } else {
+                error
+            };
+            return Err(nom::Err::Failure(error));
+        }
+        Err(e) => return Err(e),
+    };
 
     Ok((input, Expression::UnaryExp(op, Box::new(exp))))
 }
 
-pub fn parse_call_expression(input: &str) -> IResult<&str, Expression> {
+pub fn parse_call_expression(input: Input) -> PResult<Expression> {
     let (input, id) = parse_identifier(input)?;
-    let (input, args) = delimited(
-        delimited(ws, tag("("), ws),
-        separated_list0(delimited(ws, tag(","), ws), parse_expression_atomic),
-        delimited(ws, tag(")"), ws),
+    let (input, args) = preceded(
+        symbol("("),
+        cut(context(
+            "in procedure call arguments",
+            terminated(separated_list0(symbol(","), parse_expression), symbol(")")),
+        )),
     )
     .parse(input)?;
 
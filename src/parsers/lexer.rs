@@ -0,0 +1,284 @@
+use nom::multi::{many0_count, many1};
+use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::{
+    IResult, Input as _, Parser,
+    branch::alt,
+    bytes::complete::{tag, take, take_while, take_while_m_n},
+    character::complete::{alpha1, digit1, multispace1, not_line_ending},
+    combinator::{consumed, map, not, recognize, verify},
+};
+use nom_locate::LocatedSpan;
+
+use crate::parsers::syntax_error::SyntaxError;
+use crate::parsers::tokens::{Position, Token, TokenKind};
+
+// Entrada do lexer: o texto restante junto com a sua posição no código fonte
+pub type Source<'a> = LocatedSpan<&'a str>;
+
+type LResult<'a, O> = IResult<Source<'a>, O, SyntaxError>;
+
+// Palavras reservadas: não podem ser usadas como identificadores
+pub const KEYWORDS: &[&str] = &[
+    "and", "bool", "break", "call", "continue", "do", "else", "enum", "false", "for", "if", "in",
+    "int", "length", "let", "match", "move", "none", "not", "option", "or", "proc", "read",
+    "return", "skip", "some", "step", "string", "struct", "then", "true", "unit", "var", "while",
+    "write",
+];
+
+// Símbolos da linguagem, os mais longos antes dos seus prefixos
+const SYMBOLS: &[&str] = &[
+    ":=", "::", "..=", "..", "++", "**", "=>", "->", "==", "!=", "<<", ">>", "<=", ">=", "+", "-",
+    "*", "/", "%", "<", ">", "=", "&", "|", "^", "~", "(", ")", "{", "}", "[", "]", ";", ",", ":",
+    ".",
+];
+
+// Divide o código fonte em tokens. A lista sempre termina com um token Eof
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, SyntaxError> {
+    let mut tokens = Vec::new();
+    let mut input = Source::new(source);
+
+    loop {
+        let (rest, _) = ws(input).map_err(syntax_error)?;
+        if rest.is_empty() {
+            tokens.push(token(TokenKind::Eof, rest));
+            return Ok(tokens);
+        }
+
+        let (rest, token) = parse_token(rest).map_err(syntax_error)?;
+        tokens.push(token);
+        input = rest;
+    }
+}
+
+fn parse_token(input: Source) -> LResult<Token> {
+    alt((
+        parse_doc_comment,
+        parse_string,
+        parse_raw_string,
+        parse_int,
+        parse_word,
+        parse_symbol,
+        parse_unknown,
+    ))
+    .parse(input)
+}
+
+fn token<'a>(kind: TokenKind, text: Source<'a>) -> Token<'a> {
+    Token {
+        kind,
+        text: text.fragment(),
+        position: Position {
+            offset: text.location_offset(),
+            line: text.location_line(),
+            column: text.get_utf8_column(),
+        },
+    }
+}
+
+// Documentação de um procedimento: "/// texto"
+fn parse_doc_comment(input: Source) -> LResult<Token> {
+    map(consumed(doc_comment), |(text, doc)| {
+        token(TokenKind::Doc(doc.fragment().to_string()), text)
+    })
+    .parse(input)
+}
+
+// Literal string: "texto", com escapes \n, \t, \", \\ e \u{...}
+fn parse_string(input: Source) -> LResult<Token> {
+    let (mut rest, _) = tag("\"").parse(input)?;
+    let mut content = String::new();
+
+    loop {
+        let Some(c) = rest.chars().next() else {
+            let mut error = SyntaxError::expected(rest, "'\"'");
+            error.context.push("to close string literal");
+            return Err(nom::Err::Failure(error));
+        };
+
+        match c {
+            '"' => {
+                rest = rest.take_from(1);
+                break;
+            }
+            '\\' => {
+                let (after, escaped) = parse_escape(rest)?;
+                content.push(escaped);
+                rest = after;
+            }
+            c => {
+                content.push(c);
+                rest = rest.take_from(c.len_utf8());
+            }
+        }
+    }
+
+    let consumed = rest.location_offset() - input.location_offset();
+    let (rest, text) = input.take_split(consumed);
+    Ok((rest, token(TokenKind::Str(content), text)))
+}
+
+// Sequência de escape começando na barra invertida
+fn parse_escape(input: Source) -> LResult<char> {
+    let escape = input.take_from(1);
+    let (rest, c) = match escape.chars().next() {
+        Some('n') => (escape.take_from(1), '\n'),
+        Some('t') => (escape.take_from(1), '\t'),
+        Some('"') => (escape.take_from(1), '"'),
+        Some('\\') => (escape.take_from(1), '\\'),
+        Some('u') => {
+            let (rest, digits) = delimited(
+                tag("{"),
+                take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+                tag("}"),
+            )
+            .parse(escape.take_from(1))
+            .map_err(|_: nom::Err<SyntaxError>| invalid_escape(input, "\\u"))?;
+
+            let c = u32::from_str_radix(digits.fragment(), 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| invalid_escape(input, &format!("\\u{{{}}}", digits)))?;
+            (rest, c)
+        }
+        Some(c) => return Err(invalid_escape(input, &format!("\\{}", c))),
+        None => return Err(invalid_escape(input, "\\")),
+    };
+
+    Ok((rest, c))
+}
+
+fn invalid_escape(input: Source, escape: &str) -> nom::Err<SyntaxError> {
+    let mut error = SyntaxError::unexpected(input);
+    error.found = format!("'{}'", escape);
+    error.context.push("in string literal");
+    nom::Err::Failure(error)
+}
+
+// Literal string bruto: r"texto" ou r#"texto"#, sem escapes e podendo ocupar várias linhas.
+// A quantidade de '#' permite usar aspas dentro do texto
+fn parse_raw_string(input: Source) -> LResult<Token> {
+    let (after_hashes, hashes) = preceded(tag("r"), take_while(|c: char| c == '#')).parse(input)?;
+    let (body, _) = tag("\"").parse(after_hashes)?;
+
+    let closing = format!("\"{}", hashes.fragment());
+    let Some(end) = body.fragment().find(&closing) else {
+        let mut error = SyntaxError::expected(body.take_from(body.len()), format!("'{}'", closing));
+        error.context.push("to close raw string literal");
+        return Err(nom::Err::Failure(error));
+    };
+
+    let content = body.fragment()[..end].to_string();
+    let consumed = body.location_offset() - input.location_offset() + end + closing.len();
+    let (rest, text) = input.take_split(consumed);
+    Ok((rest, token(TokenKind::Str(content), text)))
+}
+
+// Literal inteiro: decimal, hexadecimal (0x), binário (0b) ou octal (0o), com '_' entre os
+// dígitos. Letras e dígitos colados ao número fazem parte do token e são validados pelo parser
+fn parse_int(input: Source) -> LResult<Token> {
+    map(
+        recognize(pair(
+            digit1,
+            take_while(|c: char| c.is_alphanumeric() || c == '_'),
+        )),
+        |text| token(TokenKind::Int, text),
+    )
+    .parse(input)
+}
+
+// Palavra reservada ou identificador. A palavra inteira é lida de uma vez,
+// então "done" é um identificador e não "do" seguido de "ne"
+fn parse_word(input: Source) -> LResult<Token> {
+    map(word, |text| {
+        let kind = if KEYWORDS.contains(text.fragment()) {
+            TokenKind::Keyword
+        } else {
+            TokenKind::Identifier
+        };
+        token(kind, text)
+    })
+    .parse(input)
+}
+
+fn word(input: Source) -> LResult<Source> {
+    recognize(pair(
+        alt((alpha1, tag("_"))),
+        take_while(|c: char| c.is_alphanumeric() || c == '_'),
+    ))
+    .parse(input)
+}
+
+fn parse_symbol(input: Source) -> LResult<Token> {
+    for symbol in SYMBOLS {
+        if let Ok((rest, text)) = tag::<_, _, SyntaxError>(*symbol).parse(input) {
+            return Ok((rest, token(TokenKind::Symbol, text)));
+        }
+    }
+    Err(nom::Err::Error(SyntaxError::unexpected(input)))
+}
+
+// Qualquer outro caractere vira um token desconhecido, reportado pelo parser
+fn parse_unknown(input: Source) -> LResult<Token> {
+    map(take(1usize), |text| token(TokenKind::Unknown, text)).parse(input)
+}
+
+// Espaços em branco e comentários
+fn ws(input: Source) -> LResult<Source> {
+    recognize(many0_count(alt((multispace1, line_comment, block_comment)))).parse(input)
+}
+
+// Comentário de linha: "// até o fim da linha". Comentários de documentação
+// que antecedem um "proc" viram tokens
+fn line_comment(input: Source) -> LResult<Source> {
+    if documents_procedure(input) {
+        return Err(nom::Err::Error(SyntaxError::unexpected(input)));
+    }
+    recognize(pair(tag("//"), not_line_ending)).parse(input)
+}
+
+// Comentário de bloco: "/* ... */", que pode conter outros comentários de bloco
+fn block_comment(input: Source) -> LResult<Source> {
+    let (mut rest, _) = tag("/*").parse(input)?;
+    let mut depth = 1;
+
+    while depth > 0 {
+        if rest.starts_with("/*") {
+            depth += 1;
+            rest = rest.take_from(2);
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            rest = rest.take_from(2);
+        } else if let Some(c) = rest.chars().next() {
+            rest = rest.take_from(c.len_utf8());
+        } else {
+            let mut error = SyntaxError::expected(rest, "'*/'");
+            error.context.push("to close block comment");
+            return Err(nom::Err::Failure(error));
+        }
+    }
+
+    let consumed = rest.location_offset() - input.location_offset();
+    Ok(input.take_split(consumed))
+}
+
+// Comentário de documentação: "/// texto". Devolve o texto depois do marcador
+fn doc_comment(input: Source) -> LResult<Source> {
+    preceded(terminated(tag("///"), not(tag("/"))), not_line_ending).parse(input)
+}
+
+// Indica se a entrada começa com comentários de documentação seguidos de "proc"
+fn documents_procedure(input: Source) -> bool {
+    preceded(
+        many1(terminated(doc_comment, ws)),
+        verify(word, |word: &Source| *word.fragment() == "proc"),
+    )
+    .parse(input)
+    .is_ok()
+}
+
+fn syntax_error(error: nom::Err<SyntaxError>) -> SyntaxError {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => SyntaxError::unexpected(Source::new("")),
+    }
+}
@@ -3,5 +3,9 @@ pub mod command_parsers;
 pub mod concret_value_parsers;
 pub mod declaration_parsers;
 pub mod expression_parsers;
+pub mod lexer;
 pub mod operators_parsers;
+pub mod pattern_parsers;
 pub mod program_parser;
+pub mod syntax_error;
+pub mod tokens;
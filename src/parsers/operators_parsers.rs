@@ -1,59 +1,101 @@
 use crate::ast::{BinaryOperator, UnaryOperator};
-use nom::{
-    IResult, Parser,
-    branch::alt,
-    bytes::complete::tag,
-    combinator::{map, value},
-    sequence::delimited,
-};
+use nom::Input as _;
 
-use crate::parsers::basic_parsers::ws;
+use crate::parsers::basic_parsers::Input;
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use crate::parsers::tokens::TokenKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,  // a - b - c = (a - b) - c
+    Right, // a ** b ** c = a ** (b ** c)
+}
+
+use Associativity::{Left, Right};
+
+// Tabela dos operadores binários: (token, operador, precedência, associatividade).
+// Quanto maior a precedência, mais forte a ligação: "a + b * c" = "a + (b * c)"
+pub const BINARY_OPERATORS: &[(&str, BinaryOperator, u8, Associativity)] = &[
+    ("or", BinaryOperator::Or, 1, Left),
+    ("and", BinaryOperator::And, 2, Left),
+    ("==", BinaryOperator::Equal, 3, Left),
+    ("!=", BinaryOperator::NotEqual, 3, Left),
+    ("<", BinaryOperator::Less, 3, Left),
+    ("<=", BinaryOperator::LessEqual, 3, Left),
+    (">", BinaryOperator::Greater, 3, Left),
+    (">=", BinaryOperator::GreaterEqual, 3, Left),
+    ("++", BinaryOperator::Concat, 4, Right),
+    ("|", BinaryOperator::BitOr, 5, Left),
+    ("^", BinaryOperator::BitXor, 6, Left),
+    ("&", BinaryOperator::BitAnd, 7, Left),
+    ("<<", BinaryOperator::Shl, 8, Left),
+    (">>", BinaryOperator::Shr, 8, Left),
+    ("+", BinaryOperator::Add, 9, Left),
+    ("-", BinaryOperator::Sub, 9, Left),
+    ("*", BinaryOperator::Mul, 10, Left),
+    ("/", BinaryOperator::Div, 10, Left),
+    ("%", BinaryOperator::Mod, 10, Left),
+    ("**", BinaryOperator::Pow, 11, Right),
+];
+
+// Tabela dos operadores unários: (token, operador, precedência do operando).
+// "not" abrange comparações ("not a == b" = "not (a == b)"), já "-" vale para o
+// termo seguinte com a sua potência, como na matemática ("-x ** 2" = "-(x ** 2)")
+pub const UNARY_OPERATORS: &[(&str, UnaryOperator, u8)] = &[
+    ("not", UnaryOperator::Not, 3),
+    ("-", UnaryOperator::Neg, 11),
+    ("~", UnaryOperator::BitNot, 11),
+    ("length", UnaryOperator::Length, 12),
+];
 
 // Binario
-pub fn precedence(op: &BinaryOperator) -> u8 {
-    match op {
-        BinaryOperator::Concat => 5,
-        BinaryOperator::Add | BinaryOperator::Sub => 3,
-        BinaryOperator::Equal
-        | BinaryOperator::Less
-        | BinaryOperator::LessEqual
-        | BinaryOperator::Greater
-        | BinaryOperator::GreaterEqual => 2,
-        BinaryOperator::And => 1,
-        BinaryOperator::Or => 0,
-    }
+pub fn precedence(op: &BinaryOperator) -> (u8, Associativity) {
+    BINARY_OPERATORS
+        .iter()
+        .find(|(_, operator, _, _)| operator == op)
+        .map(|(_, _, precedence, associativity)| (*precedence, *associativity))
+        .expect("Operador sem entrada na tabela de precedência")
 }
 
-pub fn parse_binary_operator(input: &str) -> IResult<&str, BinaryOperator> {
-    delimited(
-        ws,
-        alt((
-            value(BinaryOperator::Concat, tag("++")),
-            value(BinaryOperator::Add, tag("+")),
-            value(BinaryOperator::Sub, tag("-")),
-            value(BinaryOperator::And, tag("and")),
-            value(BinaryOperator::Or, tag("or")),
-            value(BinaryOperator::Equal, tag("==")),
-            value(BinaryOperator::LessEqual, tag("<=")),
-            value(BinaryOperator::GreaterEqual, tag(">=")),
-            value(BinaryOperator::Less, tag("<")),
-            value(BinaryOperator::Greater, tag(">")),
-        )),
-        ws,
-    )
-    .parse(input)
+pub fn unary_precedence(op: &UnaryOperator) -> u8 {
+    UNARY_OPERATORS
+        .iter()
+        .find(|(_, operator, _)| operator == op)
+        .map(|(_, _, precedence)| *precedence)
+        .expect("Operador sem entrada na tabela de precedência")
+}
+
+pub fn parse_binary_operator(input: Input) -> PResult<BinaryOperator> {
+    match operator_token(
+        input,
+        BINARY_OPERATORS.iter().map(|(text, op, _, _)| (*text, op)),
+    ) {
+        Some(op) => Ok((input.take_from(1), op.clone())),
+        None => Err(nom::Err::Error(SyntaxError::expected(input, "operator"))),
+    }
 }
 
 // Unários
-pub fn parse_unary_operator(input: &str) -> IResult<&str, UnaryOperator> {
-    delimited(
-        ws,
-        alt((
-            map(tag("-"), |_| UnaryOperator::Neg),
-            map(tag("not"), |_| UnaryOperator::Not),
-            map(tag("length"), |_| UnaryOperator::Length),
-        )),
-        ws,
-    )
-    .parse(input)
+pub fn parse_unary_operator(input: Input) -> PResult<UnaryOperator> {
+    match operator_token(
+        input,
+        UNARY_OPERATORS.iter().map(|(text, op, _)| (*text, op)),
+    ) {
+        Some(op) => Ok((input.take_from(1), op.clone())),
+        None => Err(nom::Err::Error(SyntaxError::expected(input, "operator"))),
+    }
+}
+
+// Operador da tabela correspondente ao próximo token
+fn operator_token<'t, T>(
+    input: Input,
+    mut table: impl Iterator<Item = (&'static str, &'t T)>,
+) -> Option<&'t T> {
+    let token = input.first()?;
+    if !matches!(token.kind, TokenKind::Symbol | TokenKind::Keyword) {
+        return None;
+    }
+    table
+        .find(|(text, _)| *text == token.text)
+        .map(|(_, op)| op)
 }
@@ -0,0 +1,129 @@
+use crate::ast::{ConcreteValue, Expression, Pattern};
+use crate::parsers::basic_parsers::{Input, keyword, parse_identifier, symbol};
+use crate::parsers::concret_value_parsers::{parse_bool, parse_int, parse_string};
+use crate::parsers::expression_parsers::parse_expression;
+use crate::parsers::syntax_error::{PResult, SyntaxError};
+use nom::Parser;
+use nom::branch::alt;
+use nom::combinator::{cut, map, opt};
+use nom::error::context;
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{delimited, preceded, separated_pair, terminated};
+
+// Padrão de um braço do match
+pub fn parse_pattern(input: Input) -> PResult<Pattern> {
+    alt((
+        map(alt((parse_int, parse_bool, parse_string)), |value| {
+            let ConcreteValue::Value(value) = value;
+            Pattern::Literal(value)
+        }),
+        parse_tuple_pattern,
+        map(
+            preceded(
+                keyword("some"),
+                cut(context(
+                    "in some pattern",
+                    delimited(symbol("("), parse_pattern, symbol(")")),
+                )),
+            ),
+            |pattern| Pattern::Some(Box::new(pattern)),
+        ),
+        map(keyword("none"), |_| Pattern::None),
+        parse_variant_pattern,
+        map(parse_identifier, |name| match name.as_str() {
+            "_" => Pattern::Wildcard,
+            _ => Pattern::Binding(name),
+        }),
+    ))
+    .parse(input)
+}
+
+// (a, _), ou só um padrão entre parênteses
+fn parse_tuple_pattern(input: Input) -> PResult<Pattern> {
+    map(
+        preceded(
+            symbol("("),
+            cut(context(
+                "in tuple pattern",
+                terminated(separated_list1(symbol(","), parse_pattern), symbol(")")),
+            )),
+        ),
+        |mut patterns| match patterns.len() {
+            1 => patterns.remove(0),
+            _ => Pattern::Tuple(patterns),
+        },
+    )
+    .parse(input)
+}
+
+// Forma::Circulo(r) ou Forma::Vazio
+fn parse_variant_pattern(input: Input) -> PResult<Pattern> {
+    map(
+        (
+            parse_identifier,
+            symbol("::"),
+            cut(context(
+                "in enum pattern",
+                (
+                    parse_identifier,
+                    opt(delimited(
+                        symbol("("),
+                        separated_list0(symbol(","), parse_pattern),
+                        symbol(")"),
+                    )),
+                ),
+            )),
+        ),
+        |(name, _, (variant, patterns))| {
+            Pattern::Variant(name, variant, patterns.unwrap_or_default())
+        },
+    )
+    .parse(input)
+}
+
+// Início do if let: if let padrão = expr then. Serve tanto para o comando quanto para a expressão
+pub fn parse_if_let_head(input: Input) -> PResult<(Pattern, Expression)> {
+    map(
+        preceded(
+            (keyword("if"), keyword("let")),
+            cut((
+                context("in if let pattern", parse_pattern),
+                context("after if let pattern", symbol("=")),
+                context("in if let value", parse_expression),
+                context("after if let value", keyword("then")),
+            )),
+        ),
+        |(pattern, _, value, _)| (pattern, value),
+    )
+    .parse(input)
+}
+
+// match expr { padrão => corpo, ... }, com o corpo lido pelo parser dado.
+// Serve tanto para o comando quanto para a expressão
+pub fn parse_match<'a, T, F>(
+    body: F,
+) -> impl Parser<Input<'a>, Output = (Expression, Vec<(Pattern, T)>), Error = SyntaxError>
+where
+    F: Parser<Input<'a>, Output = T, Error = SyntaxError>,
+{
+    preceded(
+        keyword("match"),
+        cut((
+            context("in match value", parse_expression),
+            delimited(
+                context("after match value", symbol("{")),
+                terminated(
+                    separated_list0(
+                        symbol(","),
+                        context(
+                            "in match arm",
+                            separated_pair(parse_pattern, symbol("=>"), body),
+                        ),
+                    ),
+                    opt(symbol(",")),
+                ),
+                context("to close match", symbol("}")),
+            ),
+        )),
+    )
+}
@@ -1,9 +1,168 @@
-use crate::ast::Program;
-use nom::{IResult, Parser, combinator::map};
-
-use crate::parsers::command_parsers::parse_command;
+use crate::ast::{Command, Declaration, Expression, IOCommand, Program};
+use crate::parsers::command_parsers::parse_statements;
+use crate::parsers::lexer::tokenize;
+use crate::parsers::syntax_error::SyntaxError;
+use crate::parsers::tokens::Tokens;
 
 // Parser do programa
-pub fn parse_program(input: &str) -> IResult<&str, Program> {
-    map(parse_command, |cmd| Program::Command(cmd)).parse(input)
+// Todos os erros de sintaxe são reunidos: cada trecho inválido vira um nó de erro
+// e a leitura continua a partir do próximo ';' ou '}'.
+pub fn parse_program(input: &str) -> Result<Program, Vec<SyntaxError>> {
+    let (program, errors) = parse_program_with_errors(input);
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+// Devolve a árvore mesmo quando há erros, junto com os erros encontrados.
+// O programa precisa ser consumido por inteiro: se sobrar algum token,
+// o erro aponta para o primeiro trecho não reconhecido.
+pub fn parse_program_with_errors(input: &str) -> (Program, Vec<SyntaxError>) {
+    let tokens = match tokenize(input) {
+        Ok(tokens) => tokens,
+        Err(error) => return (Program::Command(Command::Error(error.clone())), vec![error]),
+    };
+    let mut errors = Vec::new();
+
+    let command = match parse_statements(None)(Tokens::new(&tokens)) {
+        Ok((rest, command)) => {
+            if !rest.is_empty() {
+                errors.push(SyntaxError::unexpected(rest));
+            }
+            command
+        }
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Command::Error(e),
+        Err(nom::Err::Incomplete(_)) => Command::Error(SyntaxError::unexpected(Tokens::new(&[]))),
+    };
+
+    collect_command_errors(&command, &mut errors);
+    errors.sort_by_key(|e| e.offset);
+
+    (Program::Command(command), errors)
+}
+
+fn collect_command_errors(cmd: &Command, errors: &mut Vec<SyntaxError>) {
+    match cmd {
+        Command::Error(error) => errors.push(error.clone()),
+        Command::Located(_, cmd) => collect_command_errors(cmd, errors),
+        Command::Sequence(first, second) => {
+            collect_command_errors(first, errors);
+            collect_command_errors(second, errors);
+        }
+        Command::DeclarationBlock(declarations, body) => {
+            for decl in declarations {
+                collect_declaration_errors(decl, errors);
+            }
+            collect_command_errors(body, errors);
+        }
+        Command::WhileLoop(condition, body) => {
+            collect_expression_errors(condition, errors);
+            collect_command_errors(body, errors);
+        }
+        Command::ForLoop(_, range, body) => {
+            collect_expression_errors(&range.start, errors);
+            collect_expression_errors(&range.end, errors);
+            if let Some(step) = &range.step {
+                collect_expression_errors(step, errors);
+            }
+            collect_command_errors(body, errors);
+        }
+        Command::Labeled(_, cmd) => collect_command_errors(cmd, errors),
+        Command::IfElse(value, then_cmd, else_cmd)
+        | Command::IfLet(_, value, then_cmd, else_cmd) => {
+            collect_expression_errors(value, errors);
+            collect_command_errors(then_cmd, errors);
+            if let Some(else_cmd) = else_cmd {
+                collect_command_errors(else_cmd, errors);
+            }
+        }
+        Command::Match(value, arms) => {
+            collect_expression_errors(value, errors);
+            for (_, body) in arms {
+                collect_command_errors(body, errors);
+            }
+        }
+        Command::Assignment(_, value, _)
+        | Command::FieldAssignment(_, _, value, _)
+        | Command::Evaluate(value)
+        | Command::Return(Some(value)) => collect_expression_errors(value, errors),
+        Command::IO(IOCommand::Write(value)) => collect_expression_errors(value, errors),
+        Command::IndexAssignment(list, index, value, _) => {
+            collect_expression_errors(list, errors);
+            collect_expression_errors(index, errors);
+            collect_expression_errors(value, errors);
+        }
+        _ => {}
+    }
+}
+
+fn collect_declaration_errors(decl: &Declaration, errors: &mut Vec<SyntaxError>) {
+    match decl {
+        Declaration::Error(error) => errors.push(error.clone()),
+        Declaration::Located(_, decl) | Declaration::Documented(_, decl) => {
+            collect_declaration_errors(decl, errors)
+        }
+        Declaration::Compound(first, second) => {
+            collect_declaration_errors(first, errors);
+            collect_declaration_errors(second, errors);
+        }
+        Declaration::Procedure(_, _, _, body) => collect_command_errors(body, errors),
+        Declaration::Variable(_, _, value, _) | Declaration::Destructuring(_, value, _) => {
+            collect_expression_errors(value, errors)
+        }
+        _ => {}
+    }
+}
+
+// Expressões não têm nó de erro, mas podem conter corpos de lambda
+fn collect_expression_errors(expr: &Expression, errors: &mut Vec<SyntaxError>) {
+    match expr {
+        Expression::Lambda(_, _, body) => collect_command_errors(body, errors),
+        Expression::Located(_, expr)
+        | Expression::UnaryExp(_, expr)
+        | Expression::FieldAccess(expr, _)
+        | Expression::Some(expr) => collect_expression_errors(expr, errors),
+        Expression::BinaryExp(_, left, right) | Expression::Index(left, right) => {
+            collect_expression_errors(left, errors);
+            collect_expression_errors(right, errors);
+        }
+        Expression::ListLiteral(items)
+        | Expression::TupleLiteral(items)
+        | Expression::EnumVariant(_, _, items) => {
+            for item in items {
+                collect_expression_errors(item, errors);
+            }
+        }
+        Expression::StructLiteral(_, fields) => {
+            for (_, value) in fields {
+                collect_expression_errors(value, errors);
+            }
+        }
+        Expression::CallProcedure(call) => {
+            for arg in &call.args {
+                collect_expression_errors(arg, errors);
+            }
+        }
+        Expression::Call(callee, args) => {
+            collect_expression_errors(callee, errors);
+            for arg in args {
+                collect_expression_errors(arg, errors);
+            }
+        }
+        Expression::Match(value, arms) => {
+            collect_expression_errors(value, errors);
+            for (_, arm) in arms {
+                collect_expression_errors(arm, errors);
+            }
+        }
+        Expression::IfLet(_, value, then_expr, else_expr)
+        | Expression::IfElse(value, then_expr, else_expr) => {
+            collect_expression_errors(value, errors);
+            collect_expression_errors(then_expr, errors);
+            collect_expression_errors(else_expr, errors);
+        }
+        _ => {}
+    }
 }
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use nom::IResult;
+use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
+
+use crate::ast::Span;
+use crate::diagnostics::diagnostics::Diagnostic;
+use crate::parsers::basic_parsers::Input;
+use crate::parsers::lexer::Source;
+use crate::parsers::tokens::{Position, TokenKind, Tokens};
+
+// Resultado dos parsers da linguagem
+pub type PResult<'a, O> = IResult<Input<'a>, O, SyntaxError>;
+
+// Erro de sintaxe: onde o parser parou, o que esperava encontrar ali
+// e em que parte do programa estava (ex: "in while condition")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub offset: usize,
+    pub line: u32,
+    pub column: usize,
+    // Trecho encontrado na posição do erro
+    pub found: String,
+    pub expected: Vec<String>,
+    // Contextos, do mais interno para o mais externo
+    pub context: Vec<&'static str>,
+    // Motivo do erro quando o trecho é reconhecido mas é inválido,
+    // ex: "integer literal out of range for int"
+    pub reason: Option<&'static str>,
+}
+
+// Entradas onde um erro pode acontecer: o texto lido pelo lexer e os tokens
+// lidos pelos parsers. Informam a posição atual e o que foi encontrado nela
+pub trait ErrorInput {
+    fn error_position(&self) -> (Position, String);
+}
+
+impl ErrorInput for Source<'_> {
+    fn error_position(&self) -> (Position, String) {
+        let position = Position {
+            offset: self.location_offset(),
+            line: self.location_line(),
+            column: self.get_utf8_column(),
+        };
+        (position, found(self))
+    }
+}
+
+impl ErrorInput for Tokens<'_> {
+    fn error_position(&self) -> (Position, String) {
+        let found = match self.first() {
+            Some(token) if token.kind != TokenKind::Eof => format!("'{}'", token.text),
+            _ => END_OF_INPUT.to_string(),
+        };
+        (self.position(), found)
+    }
+}
+
+impl SyntaxError {
+    fn at(input: impl ErrorInput) -> Self {
+        let (position, found) = input.error_position();
+        SyntaxError {
+            offset: position.offset,
+            line: position.line,
+            column: position.column,
+            found,
+            expected: Vec::new(),
+            context: Vec::new(),
+            reason: None,
+        }
+    }
+
+    // Erro indicando o que era esperado na posição atual
+    pub fn expected(input: impl ErrorInput, what: impl Into<String>) -> Self {
+        let mut error = SyntaxError::at(input);
+        error.expected.push(what.into());
+        error
+    }
+
+    // Erro para um trecho que não deveria estar ali
+    pub fn unexpected(input: impl ErrorInput) -> Self {
+        SyntaxError::at(input)
+    }
+
+    // Erro para um trecho bem formado mas inválido, como um literal grande demais
+    pub fn invalid(input: impl ErrorInput, text: &str, reason: &'static str) -> Self {
+        let mut error = SyntaxError::at(input);
+        error.found = format!("'{}'", text);
+        error.reason = Some(reason);
+        error
+    }
+
+    // Mensagem sem a posição, ex: "expected 'do' after while condition"
+    pub fn message(&self) -> String {
+        let mut message = if let Some(reason) = self.reason {
+            reason.to_string()
+        } else if self.expected.is_empty() {
+            format!("unexpected {}", self.found)
+        } else {
+            format!("expected {}", join_alternatives(&self.expected))
+        };
+
+        if let Some(context) = self.context.first() {
+            message.push(' ');
+            message.push_str(context);
+        }
+
+        message
+    }
+
+    pub fn span(&self) -> Span {
+        let length = if self.found == END_OF_INPUT {
+            0
+        } else {
+            self.found.trim_matches('\'').len()
+        };
+        Span::new(self.offset, self.offset + length)
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message(), Some(self.span()))
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.message(), self.line, self.column)
+    }
+}
+
+impl<I: ErrorInput> ParseError<I> for SyntaxError {
+    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
+        SyntaxError::at(input)
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    // Entre alternativas que falharam fica o erro que avançou mais;
+    // se pararam no mesmo ponto, junta o que cada uma esperava
+    fn or(mut self, other: Self) -> Self {
+        if other.offset > self.offset {
+            return other;
+        }
+        if other.offset == self.offset {
+            for expected in other.expected {
+                if !self.expected.contains(&expected) {
+                    self.expected.push(expected);
+                }
+            }
+            if self.context.is_empty() {
+                self.context = other.context;
+            }
+        }
+        self
+    }
+}
+
+impl<I> ContextError<I> for SyntaxError {
+    fn add_context(_input: I, context: &'static str, mut other: Self) -> Self {
+        other.context.push(context);
+        other
+    }
+}
+
+impl<I: ErrorInput, E> FromExternalError<I, E> for SyntaxError {
+    fn from_external_error(input: I, kind: ErrorKind, _e: E) -> Self {
+        SyntaxError::from_error_kind(input, kind)
+    }
+}
+
+const END_OF_INPUT: &str = "end of input";
+
+// Palavra ou símbolo na posição do erro
+fn found(input: &str) -> String {
+    let word: String = input
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+
+    match input.chars().next() {
+        None => END_OF_INPUT.to_string(),
+        Some(_) if !word.is_empty() => format!("'{}'", word),
+        Some(c) => format!("'{}'", c),
+    }
+}
+
+// "a", "a or b", "a, b or c"
+fn join_alternatives(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
+    }
+}
@@ -0,0 +1,141 @@
+use std::iter::Enumerate;
+use std::slice::Iter;
+
+use nom::Needed;
+
+use crate::ast::Span;
+
+// Posição de um token no código fonte
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: u32,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,     // Palavra reservada: if, while, var, ...
+    Identifier,  // Nome de variável ou procedimento
+    Int,         // Literal inteiro, ainda em texto
+    Str(String), // Literal string, já sem as aspas
+    Symbol,      // Operadores e pontuação: ":=", "(", ";", ...
+    Doc(String), // Comentário de documentação (///) antes de um "proc"
+    Unknown,     // Caractere que não começa nenhum token
+    Eof,         // Fim do código fonte
+}
+
+// Token com o trecho do código fonte que o originou
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub position: Position,
+}
+
+impl Token<'_> {
+    pub fn span(&self) -> Span {
+        Span::new(self.position.offset, self.position.offset + self.text.len())
+    }
+
+    pub fn is_keyword(&self, word: &str) -> bool {
+        self.kind == TokenKind::Keyword && self.text == word
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+}
+
+// Entrada dos parsers: os tokens que ainda faltam ler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tokens<'a> {
+    pub tokens: &'a [Token<'a>],
+    // Posição do fim do código fonte, usada nos erros de fim de entrada
+    pub end: Position,
+}
+
+impl<'a> Tokens<'a> {
+    // Recebe a saída do lexer, que sempre termina com um token Eof
+    pub fn new(tokens: &'a [Token<'a>]) -> Self {
+        match tokens.split_last() {
+            Some((eof, tokens)) if eof.kind == TokenKind::Eof => Tokens {
+                tokens,
+                end: eof.position,
+            },
+            _ => Tokens {
+                tokens,
+                end: Position::default(),
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&'a Token<'a>> {
+        self.tokens.first()
+    }
+
+    // Posição do próximo token, ou do fim da entrada
+    pub fn position(&self) -> Position {
+        self.first().map_or(self.end, |token| token.position)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.position().offset
+    }
+}
+
+impl<'a> nom::Input for Tokens<'a> {
+    type Item = &'a Token<'a>;
+    type Iter = Iter<'a, Token<'a>>;
+    type IterIndices = Enumerate<Iter<'a, Token<'a>>>;
+
+    fn input_len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    fn take(&self, index: usize) -> Self {
+        let (tokens, rest) = self.tokens.split_at(index);
+        Tokens {
+            tokens,
+            end: rest.first().map_or(self.end, |token| token.position),
+        }
+    }
+
+    fn take_from(&self, index: usize) -> Self {
+        Tokens {
+            tokens: &self.tokens[index..],
+            end: self.end,
+        }
+    }
+
+    fn take_split(&self, index: usize) -> (Self, Self) {
+        (self.take_from(index), self.take(index))
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.tokens.iter().position(predicate)
+    }
+
+    fn iter_elements(&self) -> Self::Iter {
+        self.tokens.iter()
+    }
+
+    fn iter_indices(&self) -> Self::IterIndices {
+        self.tokens.iter().enumerate()
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        if self.tokens.len() >= count {
+            Ok(count)
+        } else {
+            Err(Needed::new(count - self.tokens.len()))
+        }
+    }
+}
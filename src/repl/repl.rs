@@ -0,0 +1,490 @@
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+use crate::ast::{Command, Declaration, Expression, ProcedureParameter, Span, Type, Value};
+use crate::diagnostics::diagnostics::Diagnostic;
+use crate::environment::environment::Environment;
+use crate::executor::environment::RuntimeEnvironment;
+use crate::executor::executor::Executor;
+use crate::parsers::basic_parsers::symbol;
+use crate::parsers::command_parsers::parse_command;
+use crate::parsers::declaration_parsers::parse_declaration;
+use crate::parsers::expression_parsers::parse_expression;
+use crate::parsers::lexer::tokenize;
+use crate::parsers::syntax_error::SyntaxError;
+use crate::parsers::tokens::Tokens;
+use crate::semantic::semantic::SemanticAnalyzer;
+
+// Nome usado no lugar do arquivo nas mensagens de erro
+const SOURCE_NAME: &str = "<repl>";
+
+const HELP: &str = "Comandos do REPL:
+    :env            Mostra os ambientes semântico e de execução
+    :type <expr>    Mostra o tipo de uma expressão sem executá-la
+    :reset          Descarta todas as variáveis e procedimentos
+    :help           Mostra esta mensagem
+    :quit           Encerra o REPL";
+
+// Entrada do REPL: declarações ficam no escopo global, comandos são executados nele
+#[derive(Debug, Clone)]
+enum Item {
+    Declaration(Declaration),
+    Command(Command),
+}
+
+// Conteúdo de cada escopo do ambiente de execução. Os escopos são restaurados no
+// lugar, já que closures guardam referências a eles
+struct RuntimeSnapshot {
+    env: Rc<RefCell<RuntimeEnvironment>>,
+    scopes: Vec<(Rc<RefCell<RuntimeEnvironment>>, RuntimeEnvironment)>,
+}
+
+impl RuntimeSnapshot {
+    fn take(env: &Rc<RefCell<RuntimeEnvironment>>) -> Self {
+        let mut scopes = Vec::new();
+        let mut current = Some(env.clone());
+        while let Some(scope) = current {
+            let contents = scope.borrow().clone();
+            current = contents.parent.clone();
+            scopes.push((scope, contents));
+        }
+        RuntimeSnapshot {
+            env: env.clone(),
+            scopes,
+        }
+    }
+
+    fn restore(self, executor: &mut Executor) {
+        for (scope, contents) in self.scopes {
+            *scope.borrow_mut() = contents;
+        }
+        executor.env = self.env;
+    }
+}
+
+// Sessão interativa. O analisador e o executor são mantidos entre as entradas,
+// então variáveis e procedimentos declarados continuam visíveis nas próximas.
+#[derive(Debug, Clone)]
+pub struct Repl {
+    pub analyzer: SemanticAnalyzer,
+    pub executor: Executor,
+    // Texto de cada entrada e a posição em que começa. As posições continuam de uma
+    // entrada para a outra, então um erro dentro de um procedimento declarado em uma
+    // entrada anterior é mostrado no texto daquela entrada
+    sources: Vec<(usize, String)>,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            analyzer: SemanticAnalyzer::new(),
+            executor: Executor::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    // Loop principal: lê da entrada até EOF ou :quit
+    pub fn run(&mut self, input: impl BufRead) {
+        let mut lines = input.lines();
+        let mut buffer = String::new();
+
+        loop {
+            print!("{}", if buffer.is_empty() { ">> " } else { ".. " });
+            let _ = std::io::stdout().flush();
+
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => break,
+            };
+
+            buffer.push_str(&line);
+            buffer.push('\n');
+
+            // Chaves abertas: continua lendo o mesmo bloco
+            if brace_depth(&buffer) > 0 {
+                continue;
+            }
+
+            let entry = std::mem::take(&mut buffer);
+            if matches!(entry.trim(), ":quit" | ":q") {
+                break;
+            }
+
+            match self.eval(&entry) {
+                Ok(output) if !output.is_empty() => println!("{}", output),
+                Ok(_) => {}
+                Err(errors) => {
+                    for error in errors {
+                        eprintln!("{}", error);
+                    }
+                }
+            }
+        }
+    }
+
+    // Avalia uma entrada completa, devolvendo o texto a ser exibido
+    pub fn eval(&mut self, input: &str) -> Result<String, Vec<String>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(String::new());
+        }
+
+        if let Some(meta) = input.strip_prefix(':') {
+            return self.eval_meta(meta);
+        }
+
+        let offset = self
+            .sources
+            .last()
+            .map_or(0, |(start, source)| start + source.len() + 1);
+        self.sources.push((offset, input.to_string()));
+        let items = parse_input(input, offset).map_err(|e| self.render(&[e.to_diagnostic()]))?;
+
+        // === ANÁLISE SEMÂNTICA ===
+        // Em caso de erro os ambientes voltam ao estado anterior à entrada
+        let snapshot = Rc::new(RefCell::new(self.analyzer.env.borrow().clone()));
+        let runtime_snapshot = RuntimeSnapshot::take(&self.executor.env);
+        self.analyzer.errors.clear();
+        for item in &items {
+            let result = match item {
+                Item::Declaration(decl) => self.analyzer.check_declaration(decl),
+                Item::Command(cmd) => self.analyzer.check_command(cmd),
+            };
+            if let Err(errors) = result {
+                self.analyzer.errors.extend(errors);
+                break;
+            }
+        }
+        if !self.analyzer.errors.is_empty() {
+            self.analyzer.env = snapshot;
+            let errors = std::mem::take(&mut self.analyzer.errors);
+            return Err(self.render(&errors));
+        }
+
+        // === EXECUÇÃO ===
+        let mut output = Vec::new();
+        for item in &items {
+            match item {
+                Item::Declaration(decl) => self.executor.execute_declaration(decl),
+                // O valor da expressão no fim do comando é exibido
+                Item::Command(cmd) => {
+                    self.executor.execute_command(cmd);
+                    match self.executor.tail_value.take() {
+                        Some(value) if value != Value::Unit && self.executor.errors.is_empty() => {
+                            output.push(value.to_string())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if !self.executor.errors.is_empty() {
+            self.analyzer.env = snapshot;
+            runtime_snapshot.restore(&mut self.executor);
+            let errors = std::mem::take(&mut self.executor.errors);
+            return Err(self.render(&errors));
+        }
+
+        Ok(output.join("\n"))
+    }
+
+    // Mostra cada erro no texto da entrada em que está a sua posição
+    fn render(&self, errors: &[Diagnostic]) -> Vec<String> {
+        errors
+            .iter()
+            .map(|error| {
+                let found = error.span.and_then(|span| {
+                    self.sources
+                        .iter()
+                        .rev()
+                        .find(|(start, _)| *start <= span.start)
+                        .map(|(start, source)| (span, *start, source))
+                });
+                match found {
+                    Some((span, start, source)) => {
+                        let span = Span::new(span.start - start, span.end - start);
+                        Diagnostic::new(error.message.clone(), Some(span))
+                            .render(source, SOURCE_NAME)
+                    }
+                    None => error.render("", SOURCE_NAME),
+                }
+            })
+            .collect()
+    }
+
+    // Verificação em uma cópia, para não marcar variáveis como movidas
+    fn type_of(&self, expr: &Expression) -> Result<String, Vec<String>> {
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.env = Rc::new(RefCell::new(self.analyzer.env.borrow().clone()));
+        let errors = match analyzer.check_expression(expr) {
+            Ok(t) if analyzer.errors.is_empty() => return Ok(t.to_string()),
+            Ok(_) => analyzer.errors,
+            Err(errors) => errors,
+        };
+        Err(errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    fn eval_meta(&mut self, meta: &str) -> Result<String, Vec<String>> {
+        let (command, argument) = meta
+            .split_once(char::is_whitespace)
+            .map(|(c, a)| (c, a.trim()))
+            .unwrap_or((meta, ""));
+
+        match command {
+            "env" => Ok(format!(
+                "== Ambiente semântico ==\n{}\n== Ambiente de execução ==\n{}",
+                describe_environment(&self.analyzer.env),
+                describe_runtime_environment(&self.executor.env)
+            )),
+            "type" => {
+                let error = match tokenize(argument) {
+                    Ok(tokens) => match parse_expression(Tokens::new(&tokens)) {
+                        Ok((rest, expr)) if rest.is_empty() => return self.type_of(&expr),
+                        Ok((rest, _)) => SyntaxError::unexpected(rest),
+                        Err(e) => syntax_error(e),
+                    },
+                    Err(e) => e,
+                };
+                Err(vec![error.to_diagnostic().render(argument, SOURCE_NAME)])
+            }
+
+            "reset" => {
+                *self = Repl::new();
+                Ok("Ambiente reiniciado.".to_string())
+            }
+            "help" => Ok(HELP.to_string()),
+            _ => Err(vec![format!(
+                "Comando desconhecido ':{}'. Use :help para ver os comandos.",
+                command
+            )]),
+        }
+    }
+}
+
+// Separa a entrada em declarações (terminadas por ';') e comandos
+// Lê uma entrada cujas posições começam em offset
+fn parse_input(input: &str, offset: usize) -> Result<Vec<Item>, SyntaxError> {
+    let mut tokens = tokenize(input).map_err(|mut error| {
+        error.offset += offset;
+        error
+    })?;
+    for token in &mut tokens {
+        token.position.offset += offset;
+    }
+    let mut items = Vec::new();
+    let mut remaining = Tokens::new(&tokens);
+
+    while !remaining.is_empty() {
+        // Uma falha depois de "var" ou "proc" já é um erro na declaração
+        let (rest, item) = match parse_declaration(remaining) {
+            Ok((rest, decl)) => (rest, Item::Declaration(decl)),
+            Err(nom::Err::Error(_)) => {
+                let (rest, cmd) = parse_command(remaining).map_err(syntax_error)?;
+                (rest, Item::Command(cmd))
+            }
+            Err(e) => return Err(syntax_error(e)),
+        };
+        items.push(item);
+
+        // Entradas seguidas precisam ser separadas por ';'
+        if rest.is_empty() {
+            break;
+        }
+        let (next, _) = symbol(";")(rest).map_err(syntax_error)?;
+        remaining = next;
+    }
+
+    Ok(items)
+}
+
+fn syntax_error(error: nom::Err<SyntaxError>) -> SyntaxError {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => SyntaxError::unexpected(Tokens::new(&[])),
+    }
+}
+
+// Quantidade de chaves abertas e ainda não fechadas, ignorando strings e comentários.
+// Um comentário de bloco aberto também faz o REPL continuar lendo
+fn brace_depth(input: &str) -> i32 {
+    let mut depth = 0;
+    let mut comment_depth = 0;
+    let mut in_string = false;
+    // Dentro de uma string bruta: quantos '#' a fecham depois do '"'
+    let mut raw_string: Option<usize> = None;
+    let mut previous = ' ';
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let before = std::mem::replace(&mut previous, c);
+
+        // Strings brutas não têm escapes
+        if let Some(hashes) = raw_string {
+            if c == '"' && chars.clone().take_while(|&c| c == '#').count() >= hashes {
+                chars.by_ref().take(hashes).for_each(drop);
+                raw_string = None;
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('r', Some('"' | '#'))
+                if !in_string && comment_depth == 0 && !is_identifier_char(before) =>
+            {
+                let hashes = chars.clone().take_while(|&c| c == '#').count();
+                if chars.clone().nth(hashes) == Some('"') {
+                    chars.by_ref().take(hashes + 1).for_each(drop);
+                    raw_string = Some(hashes);
+                }
+            }
+            ('\\', _) if in_string => {
+                chars.next();
+            }
+            _ if in_string => in_string = c != '"',
+            ('/', Some('*')) => {
+                chars.next();
+                comment_depth += 1;
+            }
+            ('*', Some('/')) if comment_depth > 0 => {
+                chars.next();
+                comment_depth -= 1;
+            }
+            _ if comment_depth > 0 => {}
+            ('/', Some('/')) => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            ('"', _) => in_string = true,
+            ('{', _) => depth += 1,
+            ('}', _) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth + comment_depth
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn describe_parameters(params: &[ProcedureParameter], return_type: &Option<Type>) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|p| format!("{} {}", p.r#type, p.identifier))
+        .collect();
+    match return_type {
+        Some(t) => format!("({}) {}", params.join(", "), t),
+        None => format!("({})", params.join(", ")),
+    }
+}
+
+// Lista os escopos do mais interno para o global
+fn describe_environment(env: &Rc<RefCell<Environment>>) -> String {
+    let mut lines = Vec::new();
+    let mut current = Some(env.clone());
+    let mut depth = 0;
+
+    while let Some(scope) = current {
+        let scope = scope.borrow();
+        lines.push(format!("escopo {}:", depth));
+
+        let mut variables: Vec<_> = scope.variables.iter().collect();
+        variables.sort_by_key(|(name, _)| *name);
+        for (name, info) in variables {
+            let moved = if info.moved { " (movida)" } else { "" };
+            lines.push(format!("  var {}: {}{}", name, info.type_, moved));
+        }
+
+        let mut structs: Vec<_> = scope.structs.iter().collect();
+        structs.sort_by_key(|(name, _)| *name);
+        for (name, fields) in structs {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|f| format!("{} {}", f.r#type, f.identifier))
+                .collect();
+            lines.push(format!("  struct {} {{ {} }}", name, fields.join(", ")));
+        }
+
+        let mut enums: Vec<_> = scope.enums.iter().collect();
+        enums.sort_by_key(|(name, _)| *name);
+        for (name, variants) in enums {
+            let variants: Vec<String> = variants
+                .iter()
+                .map(|v| match v.payload.is_empty() {
+                    true => v.identifier.clone(),
+                    false => {
+                        let payload: Vec<String> =
+                            v.payload.iter().map(|t| t.to_string()).collect();
+                        format!("{}({})", v.identifier, payload.join(", "))
+                    }
+                })
+                .collect();
+            lines.push(format!("  enum {} {{ {} }}", name, variants.join(", ")));
+        }
+
+        let mut procedures: Vec<_> = scope.procedures.iter().collect();
+        procedures.sort_by_key(|(name, _)| *name);
+        for (name, (params, return_type)) in procedures {
+            lines.push(format!(
+                "  proc {}{}",
+                name,
+                describe_parameters(params, return_type)
+            ));
+        }
+
+        current = scope.parent.clone();
+        depth += 1;
+    }
+
+    lines.join("\n")
+}
+
+fn describe_runtime_environment(env: &Rc<RefCell<RuntimeEnvironment>>) -> String {
+    let mut lines = Vec::new();
+    let mut current = Some(env.clone());
+    let mut depth = 0;
+
+    while let Some(scope) = current {
+        let scope = scope.borrow();
+        lines.push(format!("escopo {}:", depth));
+
+        let mut variables: Vec<_> = scope.variables.iter().collect();
+        variables.sort_by_key(|(name, _)| *name);
+        for (name, variable) in variables {
+            let moved = if variable.moved { " (movida)" } else { "" };
+            lines.push(format!("  {} = {}{}", name, variable.value, moved));
+        }
+
+        let mut structs: Vec<_> = scope.structs.iter().collect();
+        structs.sort_by_key(|(name, _)| *name);
+        for (name, fields) in structs {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|f| format!("{} {}", f.r#type, f.identifier))
+                .collect();
+            lines.push(format!("  struct {} {{ {} }}", name, fields.join(", ")));
+        }
+
+        let mut procedures: Vec<_> = scope.procedures.iter().collect();
+        procedures.sort_by_key(|(name, _)| *name);
+        for (name, closure) in procedures {
+            lines.push(format!(
+                "  proc {}{}",
+                name,
+                describe_parameters(&closure.params, &closure.return_type)
+            ));
+        }
+
+        current = scope.parent.clone();
+        depth += 1;
+    }
+
+    lines.join("\n")
+}
@@ -1 +1,3 @@
+pub mod patterns;
+#[allow(clippy::module_inception)]
 pub mod semantic;
@@ -0,0 +1,205 @@
+// Exaustividade e braços inalcançáveis do match, pelo algoritmo de utilidade de
+// padrões: um padrão é útil se combina com algum valor que os anteriores não cobrem.
+// As linhas são listas de padrões, uma coluna para cada valor ainda a comparar.
+use crate::ast::{Pattern, Type, Value};
+use crate::environment::environment::Environment;
+
+// Forma mais externa de um valor, que separa os padrões em grupos
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    Variant(String, String),
+    Bool(bool),
+    Tuple(usize),
+    Some,
+    None,
+    // Inteiros e strings: infinitos, nunca cobertos só por literais
+    Literal(Value),
+}
+
+type Row = Vec<Pattern>;
+
+// O padrão combina com algum valor que os padrões anteriores não cobrem?
+pub fn is_reachable(env: &Environment, previous: &[Pattern], pattern: &Pattern, t: &Type) -> bool {
+    let rows: Vec<Row> = previous.iter().map(|p| vec![p.clone()]).collect();
+    is_useful(
+        env,
+        &rows,
+        std::slice::from_ref(pattern),
+        std::slice::from_ref(t),
+    )
+}
+
+// Um valor que nenhum dos padrões cobre, escrito como padrão, se houver
+pub fn missing_pattern(env: &Environment, patterns: &[Pattern], t: &Type) -> Option<Pattern> {
+    let rows: Vec<Row> = patterns.iter().map(|p| vec![p.clone()]).collect();
+    witness(env, &rows, std::slice::from_ref(t)).map(|mut w| w.remove(0))
+}
+
+fn is_useful(env: &Environment, rows: &[Row], vector: &[Pattern], types: &[Type]) -> bool {
+    let Some((head, rest)) = vector.split_first() else {
+        return rows.is_empty();
+    };
+
+    if let Some(constructor) = constructor_of(head) {
+        let arity = field_types(env, &constructor, &types[0]);
+        return is_useful(
+            env,
+            &specialize(rows, &constructor, arity.len()),
+            &specialize_row(vector, &constructor, arity.len()).unwrap_or_default(),
+            &[arity, types[1..].to_vec()].concat(),
+        );
+    }
+
+    let used = used_constructors(rows);
+    match all_constructors(env, &types[0]) {
+        Some(all) if all.iter().all(|c| used.contains(c)) => all.iter().any(|constructor| {
+            let arity = field_types(env, constructor, &types[0]);
+            is_useful(
+                env,
+                &specialize(rows, constructor, arity.len()),
+                &specialize_row(vector, constructor, arity.len()).unwrap_or_default(),
+                &[arity, types[1..].to_vec()].concat(),
+            )
+        }),
+        _ => is_useful(env, &default_rows(rows), rest, &types[1..]),
+    }
+}
+
+// Valores, um por coluna, que nenhuma linha cobre
+fn witness(env: &Environment, rows: &[Row], types: &[Type]) -> Option<Vec<Pattern>> {
+    let Some((t, rest)) = types.split_first() else {
+        return rows.is_empty().then(Vec::new);
+    };
+
+    let used = used_constructors(rows);
+    match all_constructors(env, t) {
+        Some(all) if all.iter().all(|c| used.contains(c)) => all.iter().find_map(|constructor| {
+            let arity = field_types(env, constructor, t);
+            let n = arity.len();
+            let found = witness(
+                env,
+                &specialize(rows, constructor, n),
+                &[arity, rest.to_vec()].concat(),
+            )?;
+            Some(rebuild(constructor, found, n))
+        }),
+        all => {
+            let found = witness(env, &default_rows(rows), rest)?;
+            // Um construtor que não aparece em nenhum braço, se o tipo tem um número finito deles
+            let head = all
+                .and_then(|all| all.into_iter().find(|c| !used.contains(c)))
+                .map(|constructor| {
+                    let n = field_types(env, &constructor, t).len();
+                    build(&constructor, vec![Pattern::Wildcard; n])
+                })
+                .unwrap_or(Pattern::Wildcard);
+            Some([vec![head], found].concat())
+        }
+    }
+}
+
+fn constructor_of(pattern: &Pattern) -> Option<Constructor> {
+    match pattern {
+        Pattern::Wildcard | Pattern::Binding(_) => None,
+        Pattern::Literal(Value::Bool(b)) => Some(Constructor::Bool(*b)),
+        Pattern::Literal(value) => Some(Constructor::Literal(value.clone())),
+        Pattern::Tuple(patterns) => Some(Constructor::Tuple(patterns.len())),
+        Pattern::Variant(name, variant, _) => {
+            Some(Constructor::Variant(name.clone(), variant.clone()))
+        }
+        Pattern::Some(_) => Some(Constructor::Some),
+        Pattern::None => Some(Constructor::None),
+    }
+}
+
+// Todos os construtores do tipo, se forem finitos
+fn all_constructors(env: &Environment, t: &Type) -> Option<Vec<Constructor>> {
+    match t {
+        Type::Bool => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+        Type::Tuple(components) => Some(vec![Constructor::Tuple(components.len())]),
+        Type::Option(_) => Some(vec![Constructor::Some, Constructor::None]),
+        Type::Named(name) => env.lookup_enum(name).map(|variants| {
+            variants
+                .into_iter()
+                .map(|v| Constructor::Variant(name.clone(), v.identifier))
+                .collect()
+        }),
+        _ => None,
+    }
+}
+
+// Tipos dos valores dentro do construtor
+fn field_types(env: &Environment, constructor: &Constructor, t: &Type) -> Vec<Type> {
+    match (constructor, t) {
+        (Constructor::Variant(name, variant), _) => env
+            .lookup_enum(name)
+            .and_then(|variants| variants.into_iter().find(|v| v.identifier == *variant))
+            .map(|v| v.payload)
+            .unwrap_or_default(),
+        (Constructor::Tuple(_), Type::Tuple(components)) => components.clone(),
+        (Constructor::Tuple(n), _) => vec![Type::Unit; *n],
+        (Constructor::Some, Type::Option(inner)) => vec![(**inner).clone()],
+        (Constructor::Some, _) => vec![Type::Unit],
+        _ => Vec::new(),
+    }
+}
+
+fn used_constructors(rows: &[Row]) -> Vec<Constructor> {
+    let mut used = Vec::new();
+    for constructor in rows.iter().filter_map(|row| constructor_of(&row[0])) {
+        if !used.contains(&constructor) {
+            used.push(constructor);
+        }
+    }
+    used
+}
+
+// Linhas que combinam com o construtor, com os padrões internos no lugar da primeira coluna
+fn specialize(rows: &[Row], constructor: &Constructor, arity: usize) -> Vec<Row> {
+    rows.iter()
+        .filter_map(|row| specialize_row(row, constructor, arity))
+        .collect()
+}
+
+fn specialize_row(row: &[Pattern], constructor: &Constructor, arity: usize) -> Option<Row> {
+    let (head, rest) = row.split_first()?;
+    let mut inner = match head {
+        Pattern::Wildcard | Pattern::Binding(_) => vec![Pattern::Wildcard; arity],
+        _ if constructor_of(head).as_ref() != Some(constructor) => return None,
+        Pattern::Tuple(patterns) | Pattern::Variant(_, _, patterns) => patterns.clone(),
+        Pattern::Some(pattern) => vec![(**pattern).clone()],
+        Pattern::Literal(_) | Pattern::None => Vec::new(),
+    };
+    inner.resize(arity, Pattern::Wildcard);
+    inner.extend_from_slice(rest);
+    Some(inner)
+}
+
+// Linhas que aceitam qualquer valor na primeira coluna, sem ela
+fn default_rows(rows: &[Row]) -> Vec<Row> {
+    rows.iter()
+        .filter(|row| constructor_of(&row[0]).is_none())
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+// Junta as primeiras colunas de volta no construtor
+fn rebuild(constructor: &Constructor, mut columns: Vec<Pattern>, arity: usize) -> Vec<Pattern> {
+    let rest = columns.split_off(arity);
+    [vec![build(constructor, columns)], rest].concat()
+}
+
+fn build(constructor: &Constructor, inner: Vec<Pattern>) -> Pattern {
+    match constructor {
+        Constructor::Variant(name, variant) => {
+            Pattern::Variant(name.clone(), variant.clone(), inner)
+        }
+        Constructor::Tuple(_) => Pattern::Tuple(inner),
+        Constructor::Some => Pattern::Some(Box::new(
+            inner.into_iter().next().unwrap_or(Pattern::Wildcard),
+        )),
+        Constructor::None => Pattern::None,
+        Constructor::Bool(b) => Pattern::Literal(Value::Bool(*b)),
+        Constructor::Literal(value) => Pattern::Literal(value.clone()),
+    }
+}
@@ -1,16 +1,43 @@
-use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use crate::ast::{
-    BinaryOperator, Command, ConcreteValue, Declaration, Expression, IOCommand, Program, Type,
-    UnaryOperator, Value,
+    BinaryOperator, CallProcedure, Command, ConcreteValue, Declaration, Expression, IOCommand,
+    Pattern, ProcedureParameter, Program, Range, Span, Type, UnaryOperator, Value,
 };
 
-use crate::environment::environment::{Environment, VariableInfo};
+use crate::diagnostics::diagnostics::Diagnostic;
+use crate::environment::environment::{Binding, Environment, VariableInfo};
+use crate::semantic::patterns::{is_reachable, missing_pattern};
 
 #[derive(Debug, Clone)]
 pub struct SemanticAnalyzer {
     pub env: Rc<RefCell<Environment>>,
-    pub errors: Vec<String>,
+    pub errors: Vec<Diagnostic>,
+    // Posição do nó sendo verificado, usada nas mensagens de erro
+    pub span: Option<Span>,
+    // Loops que envolvem o comando atual, do mais externo ao mais interno, com os seus rótulos
+    pub loops: Vec<Option<String>>,
+    // Escopo em volta do loop mais interno: as variáveis dele para fora não podem
+    // ser movidas no corpo, que pode ser executado mais de uma vez
+    pub loop_scope: Option<Rc<RefCell<Environment>>>,
+    // Escopo em volta do procedimento sendo verificado e as variáveis de fora dele que
+    // o corpo usa, cada uma com o escopo em que foi declarada
+    pub closure_scope: Option<Rc<RefCell<Environment>>>,
+    pub captures: Vec<(Rc<RefCell<Environment>>, String)>,
+    // Tipo de retorno do procedimento sendo verificado; None fora de procedimentos
+    pub return_type: Option<Type>,
+    // Tipo da expressão que termina o último comando verificado, se houver
+    pub tail_type: Option<Type>,
+}
+
+impl Default for SemanticAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SemanticAnalyzer {
@@ -18,17 +45,25 @@ impl SemanticAnalyzer {
         SemanticAnalyzer {
             env: Environment::new(),
             errors: Vec::new(),
+            span: None,
+            loops: Vec::new(),
+            loop_scope: None,
+            closure_scope: None,
+            captures: Vec::new(),
+            return_type: None,
+            tail_type: None,
         }
     }
 
-    pub fn check_program(&mut self, program: &Program) -> Result<(), Vec<String>> {
+    pub fn check_program(&mut self, program: &Program) -> Result<(), Vec<Diagnostic>> {
         match program {
             Program::Command(cmd) => {
-                self.check_command(cmd)?;
-                // Ok(())
+                // Erros fatais interrompem a verificação, mas também são reportados
+                if let Err(errors) = self.check_command(cmd) {
+                    self.errors.extend(errors);
+                }
             }
         }
-        // self.check_command(program.)?;
 
         if !self.errors.is_empty() {
             Err(self.errors.clone())
@@ -37,9 +72,15 @@ impl SemanticAnalyzer {
         }
     }
 
-    pub fn check_command(&mut self, cmd: &Command) -> Result<(), Vec<String>> {
-        match cmd {
+    pub fn check_command(&mut self, cmd: &Command) -> Result<(), Vec<Diagnostic>> {
+        let result = match cmd {
             Command::Assignment(var, expr, is_move) => self.check_assignment(var, expr, *is_move),
+            Command::FieldAssignment(var, path, expr, is_move) => {
+                self.check_field_assignment(var, path, expr, *is_move)
+            }
+            Command::IndexAssignment(list, index, expr, is_move) => {
+                self.check_index_assignment(list, index, expr, *is_move)
+            }
             Command::DeclarationBlock(decls, cmd) => {
                 let old_env = self.env.clone();
                 self.env = Environment::nest(&old_env);
@@ -54,14 +95,24 @@ impl SemanticAnalyzer {
 
                 Ok(())
             }
-            Command::WhileLoop(cond, body) => {
-                let cond_type = self.check_expression(cond)?;
-                if cond_type != Type::Bool {
-                    self.report_error("Condição do while precisa ser boleana.");
+            Command::WhileLoop(cond, body) => self.check_while_loop(None, cond, body),
+            Command::ForLoop(var, range, body) => self.check_for_loop(None, var, range, body),
+            Command::Labeled(label, cmd) => match cmd.node() {
+                Command::WhileLoop(cond, body) => self.check_while_loop(Some(label), cond, body),
+                Command::ForLoop(var, range, body) => {
+                    self.check_for_loop(Some(label), var, range, body)
                 }
-
-                self.check_command(body)?;
-
+                _ => {
+                    self.report_error(format!("Rótulo '{}' só pode marcar um loop", label));
+                    self.check_command(cmd)
+                }
+            },
+            Command::Break(label) => {
+                self.check_jump("break", label);
+                Ok(())
+            }
+            Command::Continue(label) => {
+                self.check_jump("continue", label);
                 Ok(())
             }
             Command::IfElse(cond, cmd1, cmd2) => {
@@ -71,18 +122,86 @@ impl SemanticAnalyzer {
                     self.report_error("Condição do If/Else precisa ser boleana.")
                 }
 
-                self.check_command(cmd1)?;
-                self.check_command(cmd2)?;
+                let (then_type, else_type) = self.check_branches(
+                    |analyzer| {
+                        analyzer.check_command(cmd1)?;
+                        Ok(analyzer.tail_type.take())
+                    },
+                    |analyzer| match cmd2 {
+                        Some(cmd2) => {
+                            analyzer.check_command(cmd2)?;
+                            Ok(analyzer.tail_type.take())
+                        }
+                        None => Ok(None),
+                    },
+                )?;
+
+                // O valor do if é o do ramo executado. Um ramo que sempre retorna
+                // não deixa valor, então vale o do outro
+                self.tail_type = match (cmd2, then_type, else_type) {
+                    (None, _, _) => None,
+                    (Some(cmd2), then_type, _) if always_returns(cmd2) => then_type,
+                    (_, _, else_type) if always_returns(cmd1) => else_type,
+                    (_, Some(then_type), Some(else_type)) if then_type != else_type => {
+                        self.report_error(format!(
+                            "Ramos do If/Else com tipos diferentes: {} e {}",
+                            then_type, else_type
+                        ));
+                        None
+                    }
+                    (_, Some(then_type), Some(_)) => Some(then_type),
+                    _ => None,
+                };
 
                 Ok(())
             }
+            Command::Match(value, arms) => {
+                let types = self.check_match(value, arms, true, |analyzer, body| {
+                    analyzer.check_command(body)?;
+                    Ok(analyzer.tail_type.take())
+                })?;
+
+                // Como no if, um braço que sempre retorna não deixa valor, e o match
+                // só vale uma expressão se todos os outros terminam em uma do mesmo tipo
+                let values: Option<Vec<Type>> = arms
+                    .iter()
+                    .zip(types)
+                    .filter(|((_, body), _)| !always_returns(body))
+                    .map(|(_, arm_type)| arm_type)
+                    .collect();
+                self.tail_type =
+                    values.and_then(|values| self.same_type("Braços do match", values));
+                Ok(())
+            }
+            Command::IfLet(pattern, value, then_cmd, else_cmd) => {
+                // Um match com o padrão e um braço "_" para o else, sem exigir exaustividade
+                let arms = [
+                    (pattern.clone(), Some(then_cmd.as_ref())),
+                    (Pattern::Wildcard, else_cmd.as_deref()),
+                ];
+                let types = self.check_match(value, &arms, false, |analyzer, body| match body {
+                    Some(body) => {
+                        analyzer.check_command(body)?;
+                        Ok(analyzer.tail_type.take())
+                    }
+                    None => Ok(None),
+                })?;
+
+                let values: Option<Vec<Type>> = arms
+                    .iter()
+                    .zip(types)
+                    .filter(|((_, body), _)| !body.is_some_and(always_returns))
+                    .map(|(_, arm_type)| arm_type)
+                    .collect();
+                self.tail_type =
+                    values.and_then(|values| self.same_type("Ramos do if let", values));
+                Ok(())
+            }
             Command::IO(io_cmd) => match io_cmd {
                 IOCommand::Read(var) => {
-                    let var_info = self
-                        .env
-                        .borrow()
-                        .lookup_variable(var)
-                        .ok_or_else(|| vec![format!("Variável {} não declarada", var)])?;
+                    let var_info = self.lookup_variable(var).ok_or_else(|| {
+                        vec![self.error(format!("Variável {} não declarada", var))]
+                    })?;
 
                     if var_info.moved {
                         self.report_error(format!("Não pode ler uma variável movida '{}'", var));
@@ -104,9 +223,146 @@ impl SemanticAnalyzer {
             }
             Command::Skip => Ok(()),
             Command::Evaluate(expr) => {
-                let _ = self.check_expression(expr)?;
+                // Dentro de um procedimento, a expressão pode ser o valor devolvido:
+                // o tipo de retorno dá tipo a um none ou a uma lista vazia
+                let return_type = self.return_type.clone();
+                let expr_type = self.check_expression_hinted(expr, return_type.as_ref())?;
+                self.tail_type = Some(expr_type);
                 Ok(())
             }
+            Command::Return(expr) => self.check_return(expr.as_ref()),
+            Command::Located(span, cmd) => self.at(*span, |analyzer| analyzer.check_command(cmd)),
+            // Erro de sintaxe já reportado pelo parser
+            Command::Error(_) => Ok(()),
+        };
+
+        // Só uma expressão no fim de uma sequência, bloco ou ramo de if deixa um valor
+        if !matches!(
+            cmd,
+            Command::Evaluate(_)
+                | Command::Sequence(..)
+                | Command::DeclarationBlock(..)
+                | Command::IfElse(..)
+                | Command::Match(..)
+                | Command::IfLet(..)
+                | Command::Located(..)
+        ) {
+            self.tail_type = None;
+        }
+
+        result
+    }
+
+    fn check_return(&mut self, expr: Option<&Expression>) -> Result<(), Vec<Diagnostic>> {
+        let found = match (expr, self.return_type.clone()) {
+            (Some(expr), Some(expected)) => self.check_expression_as(expr, &expected)?,
+            (Some(expr), None) => self.check_expression(expr)?,
+            (None, _) => Type::Unit,
+        };
+
+        match &self.return_type {
+            None => self.report_error("return fora de um procedimento"),
+            Some(expected) if *expected != found => {
+                let message = format!(
+                    "Tipo de retorno incompatível: esperado {}, encontrado {}",
+                    expected, found
+                );
+                self.report_error(message);
+            }
+            Some(_) => {}
+        }
+
+        Ok(())
+    }
+
+    fn check_while_loop(
+        &mut self,
+        label: Option<&str>,
+        cond: &Expression,
+        body: &Command,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let cond_type = self.check_expression(cond)?;
+        if cond_type != Type::Bool {
+            self.report_error("Condição do while precisa ser boleana.");
+        }
+
+        let scope = self.env.clone();
+        self.check_loop_body(label, &scope, body)
+    }
+
+    fn check_for_loop(
+        &mut self,
+        label: Option<&str>,
+        var: &str,
+        range: &Range,
+        body: &Command,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let start_type = self.check_expression(&range.start)?;
+        let end_type = self.check_expression(&range.end)?;
+        if start_type != Type::Int || end_type != Type::Int {
+            self.report_error(format!(
+                "Limites do for precisam ser inteiros, porém foi passado {} e {}",
+                start_type, end_type
+            ));
+        }
+
+        if let Some(step) = &range.step {
+            let step_type = self.check_expression(step)?;
+            if step_type != Type::Int {
+                self.report_error(format!(
+                    "Passo do for precisa ser inteiro, porém foi passado {}",
+                    step_type
+                ));
+            }
+        }
+
+        // A variável do loop só existe no corpo
+        let old_env = self.env.clone();
+        self.env = Environment::nest(&old_env);
+        self.env.borrow_mut().variables.insert(
+            var.to_string(),
+            VariableInfo {
+                type_: Type::Int,
+                moved: false,
+                moved_fields: HashSet::new(),
+            },
+        );
+
+        let result = self.check_loop_body(label, &old_env, body);
+
+        self.env = old_env;
+        result
+    }
+
+    // Verifica o corpo de um loop; scope é o escopo em volta do loop
+    fn check_loop_body(
+        &mut self,
+        label: Option<&str>,
+        scope: &Rc<RefCell<Environment>>,
+        body: &Command,
+    ) -> Result<(), Vec<Diagnostic>> {
+        self.loops.push(label.map(str::to_string));
+        let outer_scope = self.loop_scope.replace(scope.clone());
+        let result = self.check_command(body);
+        self.loop_scope = outer_scope;
+        self.loops.pop();
+        result
+    }
+
+    // break/continue precisam estar dentro de um loop, e o rótulo, se houver,
+    // precisa ser de um dos loops que os envolvem
+    fn check_jump(&mut self, jump: &str, label: &Option<String>) {
+        match label {
+            None if self.loops.is_empty() => {
+                self.report_error(format!("{} fora de um loop", jump));
+            }
+            Some(label) if !self.loops.contains(&Some(label.clone())) => {
+                self.report_error(format!(
+                    "{} para um loop inexistente: nenhum loop com rótulo '{}'",
+                    jump, label
+                ));
+            }
+            _ => {}
         }
     }
 
@@ -115,50 +371,828 @@ impl SemanticAnalyzer {
         var: &str,
         expr: &Expression,
         is_move: bool,
-    ) -> Result<(), Vec<String>> {
-        let rhs_type = self.check_expression(expr)?;
+    ) -> Result<(), Vec<Diagnostic>> {
+        let target_type = self.lookup_variable(var).map(|info| info.type_);
+        let rhs_type = match &target_type {
+            Some(t) => self.check_expression_as(expr, t)?,
+            None => self.check_expression(expr)?,
+        };
 
         if is_move {
-            if let Expression::Identifier(source_var) = expr {
-                let (exists, already_moved) = {
-                    let env = self.env.borrow();
-                    (
-                        env.variables.contains_key(source_var),
-                        env.variables.get(source_var).map_or(false, |v| v.moved),
-                    )
-                };
-
-                if !exists {
-                    return Err(vec![format!("Variável '{}' não declarada", source_var)]);
-                } else if already_moved {
-                    return Err(vec![format!("Variável '{}' já foi movida", source_var)]);
-                }
-
-                let mut env = self.env.borrow_mut();
-                if let Some(source_info) = env.variables.get_mut(source_var) {
-                    source_info.moved = true;
+            if let Expression::Identifier(source_var) = expr.node() {
+                let source_info = self.lookup_variable(source_var);
+
+                match source_info {
+                    None => {
+                        return Err(vec![
+                            self.error(format!("Variável '{}' não declarada", source_var)),
+                        ]);
+                    }
+                    Some(info) if info.moved => {
+                        return Err(vec![
+                            self.error(format!("Variável '{}' já foi movida", source_var)),
+                        ]);
+                    }
+                    Some(_) => self.mark_variable_as_moved(source_var),
                 }
+            } else if let Some((source_var, path)) = expr.field_path() {
+                self.mark_field_as_moved(&source_var, &path);
             } else {
                 return Err(vec![
-                    "Move só pode ser aplicado a identificadores".to_string(),
+                    self.error("Move só pode ser aplicado a identificadores ou campos"),
                 ]);
             }
         }
 
-        let mut env = self.env.borrow_mut();
-        match env.variables.get_mut(var) {
+        // A variável pode ter sido declarada em um escopo externo
+        let scope = self
+            .scopes()
+            .into_iter()
+            .find(|scope| scope.borrow().variables.contains_key(var));
+        let mut scope = scope.as_ref().map(|scope| scope.borrow_mut());
+        match scope
+            .as_mut()
+            .and_then(|scope| scope.variables.get_mut(var))
+        {
             Some(var_info) => {
                 if rhs_type != var_info.type_ {
-                    return Err(vec![format!(
-                        "Tipo incompatível na atribuição de '{}': esperado {:?}, obtido {:?}",
+                    return Err(vec![self.error(format!(
+                        "Tipo incompatível na atribuição de '{}': esperado {}, obtido {}",
                         var, var_info.type_, rhs_type
-                    )]);
+                    ))]);
                 }
 
                 var_info.moved = false;
+                var_info.moved_fields.clear();
                 Ok(())
             }
-            None => Err(vec![format!("Variável '{}' não declarada", var)]),
+            None => Err(vec![
+                self.error(format!("Variável '{}' não declarada", var)),
+            ]),
+        }
+    }
+
+    pub fn check_field_assignment(
+        &mut self,
+        var: &str,
+        path: &[String],
+        expr: &Expression,
+        is_move: bool,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let var_info = self
+            .lookup_variable(var)
+            .ok_or_else(|| vec![self.error(format!("Variável '{}' não declarada", var))])?;
+
+        let target = format!("{}.{}", var, path.join("."));
+        let mut field_type = var_info.type_.clone();
+        for field in path {
+            field_type = self.field_type(&field_type, field)?;
+        }
+
+        let rhs_type = self.check_expression_as(expr, &field_type)?;
+        if is_move {
+            self.move_source(expr)?;
+        }
+        if rhs_type != field_type {
+            return Err(vec![self.error(format!(
+                "Tipo incompatível na atribuição de '{}': esperado {}, obtido {}",
+                target, field_type, rhs_type
+            ))]);
+        }
+
+        // Não há onde escrever se a variável, ou um campo que contém o destino, foi movido
+        let path = path.join(".");
+        if var_info.moved {
+            return Err(vec![self.error(format!(
+                "Atribuição a campo de variável movida: {}",
+                target
+            ))]);
+        }
+        if let Some(moved) = var_info
+            .moved_fields
+            .iter()
+            .find(|moved| path.starts_with(&format!("{}.", moved)))
+        {
+            return Err(vec![self.error(format!(
+                "Atribuição a campo de valor movido: {}.{}",
+                var, moved
+            ))]);
+        }
+
+        // O campo volta a ter valor, assim como tudo dentro dele
+        self.update_variable(var, |info| {
+            info.moved_fields
+                .retain(|moved| *moved != path && !moved.starts_with(&format!("{}.", path)))
+        });
+        Ok(())
+    }
+
+    pub fn check_index_assignment(
+        &mut self,
+        list: &Expression,
+        index: &Expression,
+        expr: &Expression,
+        is_move: bool,
+    ) -> Result<(), Vec<Diagnostic>> {
+        let list_type = self.check_expression(list)?;
+        self.check_index(index)?;
+        let Type::List(element_type) = list_type else {
+            return Err(vec![self.error(format!(
+                "Atribuição por índice em algo que não é lista: {}",
+                list_type
+            ))]);
+        };
+
+        let rhs_type = self.check_expression_as(expr, &element_type)?;
+        if is_move {
+            self.move_source(expr)?;
+        }
+        if rhs_type != *element_type {
+            return Err(vec![self.error(format!(
+                "Tipo incompatível na atribuição de elemento: esperado {}, obtido {}",
+                element_type, rhs_type
+            ))]);
+        }
+        Ok(())
+    }
+
+    fn check_index(&mut self, index: &Expression) -> Result<(), Vec<Diagnostic>> {
+        let index_type = self.check_expression(index)?;
+        if index_type != Type::Int {
+            self.report_error(format!(
+                "Índice de lista deve ser int, obtido {}",
+                index_type
+            ));
+        }
+        Ok(())
+    }
+
+    // Move a origem de uma atribuição: a variável inteira ou só um campo
+    fn move_source(&mut self, expr: &Expression) -> Result<(), Vec<Diagnostic>> {
+        match expr.field_path() {
+            Some((source_var, path)) if path.is_empty() => self.mark_variable_as_moved(&source_var),
+            Some((source_var, path)) => self.mark_field_as_moved(&source_var, &path),
+            None => {
+                return Err(vec![
+                    self.error("Move só pode ser aplicado a identificadores ou campos"),
+                ]);
+            }
+        }
+        Ok(())
+    }
+
+    // Verifica a expressão sabendo o tipo esperado, o que dá tipo a uma lista vazia
+    pub fn check_expression_as(
+        &mut self,
+        expr: &Expression,
+        expected: &Type,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        match (expr.node(), expected) {
+            (Expression::ListLiteral(elements), Type::List(element_type)) => {
+                self.check_list_literal(elements, Some(element_type))
+            }
+            (Expression::TupleLiteral(components), Type::Tuple(types))
+                if components.len() == types.len() =>
+            {
+                let mut found = Vec::new();
+                for (component, t) in components.iter().zip(types) {
+                    found.push(self.check_expression_as(component, t)?);
+                }
+                Ok(Type::Tuple(found))
+            }
+            (Expression::None, Type::Option(_)) => Ok(expected.clone()),
+            (Expression::Some(value), Type::Option(inner)) => Ok(Type::Option(Box::new(
+                self.check_expression_as(value, inner)?,
+            ))),
+            // O tipo esperado vale para cada ramo, como em "if c then some(x) else none"
+            (Expression::IfElse(cond, then_expr, else_expr), _) => {
+                self.check_if_expression(cond, then_expr, else_expr, Some(expected))
+            }
+            (Expression::Match(value, arms), _) => {
+                self.check_match_expression(value, arms, Some(expected))
+            }
+            (Expression::IfLet(pattern, value, then_expr, else_expr), _) => {
+                self.check_if_let_expression(pattern, value, then_expr, else_expr, Some(expected))
+            }
+            _ => self.check_expression(expr),
+        }
+    }
+
+    // Verifica a expressão com o tipo esperado, se houver
+    fn check_expression_hinted(
+        &mut self,
+        expr: &Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        match expected {
+            Some(expected) => self.check_expression_as(expr, expected),
+            None => self.check_expression(expr),
+        }
+    }
+
+    // Tipo comum aos ramos, reportando o primeiro que difere
+    fn same_type(&mut self, branches: &str, types: Vec<Type>) -> Option<Type> {
+        if let Some(other) = types.iter().find(|t| **t != types[0]) {
+            self.report_error(format!(
+                "{} com tipos diferentes: {} e {}",
+                branches, types[0], other
+            ));
+        }
+        types.into_iter().next()
+    }
+
+    fn check_if_expression(
+        &mut self,
+        cond: &Expression,
+        then_expr: &Expression,
+        else_expr: &Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let cond_type = self.check_expression(cond)?;
+        if cond_type != Type::Bool {
+            self.report_error("Condição do If/Else precisa ser boleana.");
+        }
+
+        let (then_type, else_type) = self.check_branches(
+            |analyzer| analyzer.check_expression_hinted(then_expr, expected),
+            |analyzer| analyzer.check_expression_hinted(else_expr, expected),
+        )?;
+        if then_type != else_type {
+            self.report_error(format!(
+                "Ramos do If/Else com tipos diferentes: {} e {}",
+                then_type, else_type
+            ));
+        }
+
+        Ok(then_type)
+    }
+
+    fn check_match_expression(
+        &mut self,
+        value: &Expression,
+        arms: &[(Pattern, Expression)],
+        expected: Option<&Type>,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let types = self.check_match(value, arms, true, |analyzer, body| {
+            analyzer.check_expression_hinted(body, expected).map(Some)
+        })?;
+
+        let types = types.into_iter().flatten().collect();
+        self.same_type("Braços do match", types)
+            .ok_or_else(|| vec![self.error("match sem braços não produz valor")])
+    }
+
+    fn check_if_let_expression(
+        &mut self,
+        pattern: &Pattern,
+        value: &Expression,
+        then_expr: &Expression,
+        else_expr: &Expression,
+        expected: Option<&Type>,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let arms = [(pattern.clone(), then_expr), (Pattern::Wildcard, else_expr)];
+        let types = self.check_match(value, &arms, false, |analyzer, body| {
+            analyzer.check_expression_hinted(body, expected).map(Some)
+        })?;
+
+        let types = types.into_iter().flatten().collect();
+        self.same_type("Ramos do if let", types)
+            .ok_or_else(|| vec![self.error("if let sem valor")])
+    }
+
+    fn check_list_literal(
+        &mut self,
+        elements: &[Expression],
+        expected: Option<&Type>,
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let mut element_type = expected.cloned();
+        for element in elements {
+            let found = match &element_type {
+                Some(t) => self.check_expression_as(element, t)?,
+                None => self.check_expression(element)?,
+            };
+            match &element_type {
+                Some(t) if *t != found => self.report_error(format!(
+                    "Elementos da lista com tipos diferentes: esperado {}, obtido {}",
+                    t, found
+                )),
+                Some(_) => {}
+                None => element_type = Some(found),
+            }
+        }
+
+        match element_type {
+            Some(t) => Ok(Type::List(Box::new(t))),
+            None => Err(vec![self.error(
+                "Tipo da lista vazia não pode ser inferido: declare o tipo, como em var xs: [int] = []",
+            )]),
+        }
+    }
+
+    // push e pop recebem a lista pelo lugar em que ela está e a alteram ali mesmo
+    fn check_builtin(&mut self, call: &CallProcedure) -> Result<Type, Vec<Diagnostic>> {
+        let expected_args = if call.id == "push" { 2 } else { 1 };
+        if call.args.len() != expected_args {
+            return Err(vec![self.error(format!(
+                "Número incorreto de argumentos para {}: esperado {}, obtido {}",
+                call.id,
+                expected_args,
+                call.args.len()
+            ))]);
+        }
+
+        let list = &call.args[0];
+        if !list.is_place() {
+            return Err(vec![self.error(format!(
+                "O primeiro argumento de {} deve ser uma variável, um campo ou um elemento",
+                call.id
+            ))]);
+        }
+        let list_type = self.check_expression(list)?;
+        let Type::List(element_type) = list_type else {
+            return Err(vec![self.error(format!(
+                "{} espera uma lista, obtido {}",
+                call.id, list_type
+            ))]);
+        };
+
+        if call.id == "pop" {
+            return Ok(*element_type);
+        }
+
+        // O valor passa a pertencer à lista, como um argumento de procedimento
+        let value = &call.args[1];
+        let value_type = self.check_expression_as(value, &element_type)?;
+        if value_type != *element_type {
+            self.report_error(format!(
+                "Tipo inválido para argumento 2 em push: esperado {}, obtido {}",
+                element_type, value_type
+            ));
+        }
+        if value.field_path().is_some() {
+            self.move_source(value)?;
+        }
+        Ok(Type::Unit)
+    }
+
+    fn check_struct_literal(
+        &mut self,
+        name: &str,
+        fields: &[(String, Expression)],
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let declared = self
+            .env
+            .borrow()
+            .lookup_struct(name)
+            .ok_or_else(|| vec![self.error(format!("Struct não declarada: {}", name))])?;
+
+        let mut seen = HashSet::new();
+        for (field, expr) in fields {
+            let expr_type = match declared.iter().find(|d| d.identifier == *field) {
+                Some(d) => self.check_expression_as(expr, &d.r#type)?,
+                None => self.check_expression(expr)?,
+            };
+
+            if !seen.insert(field.as_str()) {
+                self.report_error(format!("Campo '{}' repetido em {}", field, name));
+                continue;
+            }
+
+            match declared.iter().find(|d| d.identifier == *field) {
+                None => self.report_error(format!("Campo '{}' não existe em {}", field, name)),
+                Some(d) if d.r#type != expr_type => self.report_error(format!(
+                    "Tipo inválido para o campo {} de {}: esperado {}, obtido {}",
+                    field, name, d.r#type, expr_type
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for d in &declared {
+            if !seen.contains(d.identifier.as_str()) {
+                self.report_error(format!("Campo '{}' faltando em {}", d.identifier, name));
+            }
+        }
+
+        Ok(Type::Named(name.to_string()))
+    }
+
+    // Tipo de um campo de uma struct
+    fn field_type(&self, object_type: &Type, field: &str) -> Result<Type, Vec<Diagnostic>> {
+        // Componente de tupla, pela posição
+        if let Type::Tuple(components) = object_type {
+            return field
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| components.get(i).cloned())
+                .ok_or_else(|| {
+                    vec![self.error(format!(
+                        "Tupla {} não tem o componente {}",
+                        object_type, field
+                    ))]
+                });
+        }
+
+        let Type::Named(name) = object_type else {
+            return Err(vec![self.error(format!(
+                "Acesso ao campo '{}' de algo que não é struct nem tupla: {}",
+                field, object_type
+            ))]);
+        };
+
+        let declared = self.env.borrow().lookup_struct(name);
+        let declared = match declared {
+            Some(declared) => declared,
+            None if self.env.borrow().lookup_enum(name).is_some() => {
+                return Err(vec![self.error(format!(
+                    "Acesso ao campo '{}' de algo que não é struct nem tupla: {}",
+                    field, object_type
+                ))]);
+            }
+            None => return Err(vec![self.error(format!("Struct não declarada: {}", name))]),
+        };
+
+        declared
+            .into_iter()
+            .find(|d| d.identifier == field)
+            .map(|d| d.r#type)
+            .ok_or_else(|| vec![self.error(format!("Campo '{}' não existe em {}", field, name))])
+    }
+
+    // Structs e enums usados como tipo precisam ter sido declarados antes
+    fn check_type_exists(&mut self, r#type: &Type) {
+        match r#type {
+            Type::Named(name) if !self.type_declared(name) => {
+                self.report_error(format!("Tipo não declarado: {}", name));
+            }
+            Type::List(element_type) | Type::Option(element_type) => {
+                self.check_type_exists(element_type)
+            }
+            Type::Proc(params, return_type) => {
+                for param in params {
+                    self.check_type_exists(param);
+                }
+                self.check_type_exists(return_type);
+            }
+            Type::Tuple(components) => {
+                for component in components {
+                    self.check_type_exists(component);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn type_declared(&self, name: &str) -> bool {
+        let env = self.env.borrow();
+        env.lookup_struct(name).is_some() || env.lookup_enum(name).is_some()
+    }
+
+    fn check_enum_variant(
+        &mut self,
+        name: &str,
+        variant: &str,
+        values: &[Expression],
+    ) -> Result<Type, Vec<Diagnostic>> {
+        let variants = self
+            .env
+            .borrow()
+            .lookup_enum(name)
+            .ok_or_else(|| vec![self.error(format!("Enum não declarado: {}", name))])?;
+        let declared = variants
+            .into_iter()
+            .find(|v| v.identifier == variant)
+            .ok_or_else(|| {
+                vec![self.error(format!("Variante '{}' não existe em {}", variant, name))]
+            })?;
+
+        if values.len() != declared.payload.len() {
+            return Err(vec![self.error(format!(
+                "Número incorreto de valores para {}::{}: esperado {}, obtido {}",
+                name,
+                variant,
+                declared.payload.len(),
+                values.len()
+            ))]);
+        }
+
+        // Os valores são copiados para a variante, como os campos de uma struct
+        for (i, (value, expected)) in values.iter().zip(&declared.payload).enumerate() {
+            let found = self.check_expression_as(value, expected)?;
+            if found != *expected {
+                self.report_error(format!(
+                    "Tipo inválido para o valor {} de {}::{}: esperado {}, obtido {}",
+                    i + 1,
+                    name,
+                    variant,
+                    expected,
+                    found
+                ));
+            }
+        }
+
+        Ok(Type::Named(name.to_string()))
+    }
+
+    // Verifica os parâmetros e o corpo de um procedimento, declarado ou anônimo
+    // (sem nome), no ambiente em que ele é criado
+    fn check_procedure(
+        &mut self,
+        name: Option<&str>,
+        params: &[ProcedureParameter],
+        return_type: Option<&Type>,
+        body: &Command,
+    ) -> Result<(), Vec<Diagnostic>> {
+        // Verificar parâmetros duplicados
+        let mut param_names = HashSet::new();
+        for param in params {
+            if param_names.contains(&param.identifier) {
+                self.report_error(format!("Parâmetro duplicado: {}", param.identifier));
+            }
+            param_names.insert(param.identifier.clone());
+            self.check_type_exists(&param.r#type);
+        }
+        if let Some(return_type) = return_type {
+            self.check_type_exists(return_type);
+        }
+
+        // O corpo pode ler e alterar as variáveis de fora, mas não movê-las:
+        // o procedimento pode ser chamado mais de uma vez
+        let before = self.moved_state();
+
+        // Criar novo escopo para o corpo
+        let old_env = self.env.clone();
+        self.env = Environment::nest(&old_env);
+        let outer_closure_scope = self.closure_scope.replace(old_env.clone());
+        let outer_captures = std::mem::take(&mut self.captures);
+
+        // Adicionar parâmetros ao ambiente
+        for param in params {
+            self.env.borrow_mut().variables.insert(
+                param.identifier.clone(),
+                VariableInfo {
+                    type_: param.r#type.clone(),
+                    moved: false,
+                    moved_fields: HashSet::new(),
+                },
+            );
+        }
+
+        // Verificar corpo do procedimento. Os loops de fora não são alvo de
+        // break/continue dentro dele
+        let outer_loops = std::mem::take(&mut self.loops);
+        let outer_loop_scope = self.loop_scope.take();
+        let outer_return = self
+            .return_type
+            .replace(return_type.cloned().unwrap_or(Type::Unit));
+        let result = self.check_command(body);
+        let tail_type = self.tail_type.take();
+        self.loops = outer_loops;
+        self.loop_scope = outer_loop_scope;
+        self.return_type = outer_return;
+        self.env = old_env;
+        self.closure_scope = outer_closure_scope;
+        let captures = std::mem::replace(&mut self.captures, outer_captures);
+        result?;
+
+        let by = name.map_or("por um procedimento anônimo".to_string(), |name| {
+            format!("pelo procedimento '{}'", name)
+        });
+        for (scope, var) in captures {
+            scope.borrow_mut().captured.entry(var).or_insert(by.clone());
+        }
+
+        let after = self.moved_state();
+        for (scope_before, scope_after) in before.iter().zip(&after) {
+            let mut moved: Vec<&String> = scope_after
+                .iter()
+                .filter(|(name, info)| scope_before.get(*name) != Some(*info))
+                .map(|(name, _)| name)
+                .collect();
+            moved.sort();
+            for moved_name in moved {
+                self.report_error(format!(
+                    "{} não pode mover a variável capturada '{}'",
+                    name.map_or("Procedimento anônimo".to_string(), |name| {
+                        format!("Procedimento '{}'", name)
+                    }),
+                    moved_name
+                ));
+            }
+        }
+        self.set_moved_state(&before);
+
+        // Todo caminho que não termina em return precisa terminar
+        // com uma expressão do tipo declarado
+        if let Some(declared_type) = return_type
+            && !always_returns(body)
+        {
+            match tail_type {
+                None => self.report_error(format!(
+                    "missing return: nem todo caminho de {} devolve um valor do tipo {}",
+                    name.map_or("procedimento anônimo".to_string(), |name| format!(
+                        "'{}'",
+                        name
+                    )),
+                    declared_type
+                )),
+                Some(body_type) if body_type != *declared_type => self.report_error(format!(
+                    "Tipo de retorno incompatível: esperado {}, encontrado {}",
+                    declared_type, body_type
+                )),
+                Some(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // Verifica o valor e os braços de um match, cada braço a partir do mesmo estado de
+    // ownership. Devolve o tipo deixado por cada braço
+    fn check_match<T>(
+        &mut self,
+        value: &Expression,
+        arms: &[(Pattern, T)],
+        coverage: bool,
+        mut check_body: impl FnMut(&mut Self, &T) -> Result<Option<Type>, Vec<Diagnostic>>,
+    ) -> Result<Vec<Option<Type>>, Vec<Diagnostic>> {
+        let value_type = self.check_expression(value)?;
+
+        let errors_before = self.errors.len();
+        let mut arm_bindings = Vec::new();
+        for (pattern, _) in arms {
+            let mut bindings = Vec::new();
+            self.check_pattern(pattern, &value_type, &mut bindings);
+            arm_bindings.push(bindings);
+        }
+
+        // Com os padrões bem tipados, verifica se cobrem todos os valores e se cada um é útil
+        if coverage && self.errors.len() == errors_before {
+            let patterns: Vec<Pattern> = arms.iter().map(|(pattern, _)| pattern.clone()).collect();
+            let mut messages = Vec::new();
+            {
+                let env = self.env.borrow();
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if !is_reachable(&env, &patterns[..i], pattern, &value_type) {
+                        messages.push(format!("Braço do match inalcançável: {}", pattern));
+                    }
+                }
+                if let Some(missing) = missing_pattern(&env, &patterns, &value_type) {
+                    messages.push(format!(
+                        "match não exaustivo: o padrão {} não é coberto",
+                        missing
+                    ));
+                }
+            }
+            for message in messages {
+                self.report_error(message);
+            }
+        }
+
+        let before = self.moved_state();
+        let mut after: Option<Vec<HashMap<String, VariableInfo>>> = None;
+        let mut types = Vec::new();
+        for ((_, body), bindings) in arms.iter().zip(arm_bindings) {
+            self.set_moved_state(&before);
+
+            // Guardar um valor que não é Copy tira o valor do match, como um var com move
+            if bindings.iter().any(|(_, t)| !is_copy(t)) && value.field_path().is_some() {
+                self.move_source(value)?;
+            }
+
+            let old_env = self.env.clone();
+            self.env = Environment::nest(&old_env);
+            for (name, type_) in bindings {
+                self.env.borrow_mut().variables.insert(
+                    name,
+                    VariableInfo {
+                        type_,
+                        moved: false,
+                        moved_fields: HashSet::new(),
+                    },
+                );
+            }
+            let result = check_body(self, body);
+            self.env = old_env;
+            types.push(result?);
+
+            let state = self.moved_state();
+            after = Some(match after {
+                Some(mut merged) => {
+                    merge_moved_state(&mut merged, state);
+                    merged
+                }
+                None => state,
+            });
+        }
+        self.set_moved_state(&after.unwrap_or(before));
+
+        Ok(types)
+    }
+
+    // Verifica o padrão contra o tipo do valor, juntando as variáveis que ele declara
+    fn check_pattern(&mut self, pattern: &Pattern, t: &Type, bindings: &mut Vec<(String, Type)>) {
+        let mismatch =
+            |pattern: &Pattern| format!("Padrão {} não combina com o tipo {}", pattern, t);
+
+        match (pattern, t) {
+            (Pattern::Wildcard, _) => {}
+            (Pattern::Binding(name), _) => {
+                if bindings.iter().any(|(bound, _)| bound == name) {
+                    self.report_error(format!("Variável repetida no padrão: {}", name));
+                }
+                bindings.push((name.clone(), t.clone()));
+            }
+            (Pattern::Literal(value), _) => {
+                let literal_type = match value {
+                    Value::Int(_) => Type::Int,
+                    Value::Str(_) => Type::Str,
+                    _ => Type::Bool,
+                };
+                if literal_type != *t {
+                    self.report_error(mismatch(pattern));
+                }
+            }
+            (Pattern::Tuple(patterns), Type::Tuple(components))
+                if patterns.len() == components.len() =>
+            {
+                for (pattern, component) in patterns.iter().zip(components) {
+                    self.check_pattern(pattern, component, bindings);
+                }
+            }
+            (Pattern::Variant(name, variant, patterns), Type::Named(type_name))
+                if name == type_name =>
+            {
+                let Some(variants) = self.env.borrow().lookup_enum(name) else {
+                    self.report_error(format!("Enum não declarado: {}", name));
+                    return;
+                };
+                let Some(declared) = variants.into_iter().find(|v| v.identifier == *variant) else {
+                    self.report_error(format!("Variante '{}' não existe em {}", variant, name));
+                    return;
+                };
+                if patterns.len() != declared.payload.len() {
+                    self.report_error(format!(
+                        "Número incorreto de valores no padrão {}: esperado {}, obtido {}",
+                        pattern,
+                        declared.payload.len(),
+                        patterns.len()
+                    ));
+                    return;
+                }
+                for (pattern, payload) in patterns.iter().zip(&declared.payload) {
+                    self.check_pattern(pattern, payload, bindings);
+                }
+            }
+            (Pattern::Some(pattern), Type::Option(inner)) => {
+                self.check_pattern(pattern, inner, bindings)
+            }
+            (Pattern::None, Type::Option(_)) => {}
+            _ => self.report_error(mismatch(pattern)),
+        }
+    }
+
+    // Marca só o campo como movido; o restante da struct continua disponível
+    pub fn mark_field_as_moved(&mut self, var: &str, path: &[String]) {
+        self.check_move(var);
+        self.move_field(var, path);
+    }
+
+    fn move_field(&mut self, var: &str, path: &[String]) {
+        // Mover uma tupla move só os componentes que não são Copy
+        if let Some(Type::Tuple(components)) = self.place_type(var, path) {
+            for (i, component) in components.iter().enumerate() {
+                if !is_copy(component) {
+                    let mut component_path = path.to_vec();
+                    component_path.push(i.to_string());
+                    self.move_field(var, &component_path);
+                }
+            }
+            return;
+        }
+
+        let path = path.join(".");
+        self.update_variable(var, |info| {
+            info.moved_fields.insert(path);
+        });
+    }
+
+    // Tipo do valor no caminho de campos, sem reportar erros
+    fn place_type(&self, var: &str, path: &[String]) -> Option<Type> {
+        let mut place_type = self.env.borrow().lookup_variable(var)?.type_;
+        for field in path {
+            place_type = self.field_type(&place_type, field).ok()?;
+        }
+        Some(place_type)
+    }
+
+    // Altera a variável no escopo em que ela foi declarada
+    fn update_variable(&self, var: &str, update: impl FnOnce(&mut VariableInfo)) {
+        for scope in self.scopes() {
+            if let Some(info) = scope.borrow_mut().variables.get_mut(var) {
+                update(info);
+                return;
+            }
         }
     }
 
@@ -170,6 +1204,12 @@ impl SemanticAnalyzer {
     }
 
     pub fn mark_variable_as_moved(&mut self, var: &str) {
+        self.check_move(var);
+        if let Some(Type::Tuple(_)) = self.place_type(var, &[]) {
+            self.move_field(var, &[]);
+            return;
+        }
+
         let mut current_env = self.env.clone();
 
         loop {
@@ -196,35 +1236,34 @@ impl SemanticAnalyzer {
         }
     }
 
-    fn get_last_expression_type(&mut self, cmd: &Command) -> Type {
-        match cmd {
-            Command::Sequence(_, cmd2) => self.get_last_expression_type(cmd2),
-            Command::Evaluate(expr) => self.check_expression(expr).unwrap_or(Type::Unit),
-            _ => Type::Unit,
-        }
-    }
-
-    pub fn check_declaration(&mut self, decl: &Declaration) -> Result<(), Vec<String>> {
+    pub fn check_declaration(&mut self, decl: &Declaration) -> Result<(), Vec<Diagnostic>> {
         match decl {
-            Declaration::Variable(name, expr, is_move) => {
-                let expr_type = self.check_expression(expr)?;
+            Declaration::Variable(name, declared_type, expr, is_move) => {
+                let expr_type = match declared_type {
+                    Some(t) => {
+                        self.check_type_exists(t);
+                        let found = self.check_expression_as(expr, t)?;
+                        if found != *t {
+                            self.report_error(format!(
+                                "Tipo incompatível na declaração de '{}': esperado {}, obtido {}",
+                                name, t, found
+                            ));
+                        }
+                        t.clone()
+                    }
+                    None => self.check_expression(expr)?,
+                };
 
                 // Duplicata
-                if self.env.borrow().variables.contains_key(name) {
-                    self.report_error(format!("Variável já declarada: {}", name));
-                }
+                self.check_new_variable(name);
 
                 // Tratamento movimentacoes
                 if *is_move {
-                    if let Expression::Identifier(source_var) = expr {
+                    if let Expression::Identifier(source_var) = expr.node() {
                         // Verifica a variável fonte
-                        let source_info = self
-                            .env
-                            .borrow()
-                            .lookup_variable(source_var)
-                            .ok_or_else(|| {
-                                vec![format!("Variável não declarada: {}", source_var)]
-                            })?;
+                        let source_info = self.lookup_variable(source_var).ok_or_else(|| {
+                            vec![self.error(format!("Variável não declarada: {}", source_var))]
+                        })?;
 
                         if source_info.moved {
                             self.report_error(format!("Variável '{}' já foi movida", source_var));
@@ -232,9 +1271,12 @@ impl SemanticAnalyzer {
 
                         // Marca a variável fonte como movida
                         self.mark_variable_as_moved(source_var);
+                    } else if let Some((source_var, path)) = expr.field_path() {
+                        // Só o campo fica movido, o restante da struct continua disponível
+                        self.mark_field_as_moved(&source_var, &path);
                     } else {
                         self.report_error(
-                            "Move só pode ser aplicado a identificadores".to_string(),
+                            "Move só pode ser aplicado a identificadores ou campos".to_string(),
                         );
                     }
                 }
@@ -245,90 +1287,245 @@ impl SemanticAnalyzer {
                     VariableInfo {
                         type_: expr_type,
                         moved: false,
+                        moved_fields: HashSet::new(),
                     },
                 );
 
                 Ok(())
             }
             Declaration::Procedure(name, params, return_type, body) => {
-                // Verificar parâmetros duplicados
-                let mut param_names = HashSet::new();
-                for param in params {
-                    if param_names.contains(&param.identifier) {
-                        self.report_error(format!("Parâmetro duplicado: {}", param.identifier));
-                    }
-                    param_names.insert(param.identifier.clone());
+                // Uma chamada usa o nome declarado mais próximo, que precisa ser um só
+                if self.env.borrow().variables.contains_key(name) {
+                    self.report_error(format!(
+                        "Nome já usado por uma variável neste escopo: {}",
+                        name
+                    ));
                 }
 
-                // Registrar procedimento no ambiente com tipo de retorno
+                // Registrado antes do corpo, para permitir recursão
                 self.env
                     .borrow_mut()
                     .procedures
                     .insert(name.clone(), (params.clone(), return_type.clone()));
 
-                // Criar novo escopo para o corpo
-                let old_env = self.env.clone();
-                self.env = Environment::nest(&old_env);
+                self.check_procedure(Some(name), params, return_type.as_ref(), body)
+            }
 
-                // Adicionar parâmetros ao ambiente
-                for param in params {
+            Declaration::Destructuring(names, expr, is_move) => {
+                let expr_type = self.check_expression(expr)?;
+                let components = match expr_type {
+                    Type::Tuple(components) if components.len() == names.len() => components,
+                    other => {
+                        return Err(vec![self.error(format!(
+                            "Desestruturação espera uma tupla com {} componentes, obtido {}",
+                            names.len(),
+                            other
+                        ))]);
+                    }
+                };
+
+                let mut seen = HashSet::new();
+                for name in names {
+                    if !seen.insert(name) {
+                        self.report_error(format!(
+                            "Variável repetida na desestruturação: {}",
+                            name
+                        ));
+                    } else {
+                        self.check_new_variable(name);
+                    }
+                }
+
+                // Com move, os componentes que não são Copy deixam a tupla de origem
+                if *is_move {
+                    self.move_source(expr)?;
+                }
+
+                for (name, type_) in names.iter().zip(components) {
                     self.env.borrow_mut().variables.insert(
-                        param.identifier.clone(),
+                        name.clone(),
                         VariableInfo {
-                            type_: param.r#type.clone(),
+                            type_,
                             moved: false,
+                            moved_fields: HashSet::new(),
                         },
                     );
                 }
+                Ok(())
+            }
+            Declaration::Struct(name, fields) => {
+                if self.env.borrow().structs.contains_key(name) {
+                    self.report_error(format!("Struct já declarada: {}", name));
+                } else if self.env.borrow().enums.contains_key(name) {
+                    self.report_error(format!("Tipo já declarado: {}", name));
+                }
 
-                // Verificar corpo do procedimento
-                self.check_command(body)?;
-
-                // Verificar tipo de retorno se necessário
-                if let Some(declared_type) = return_type {
-                    let body_type = self.get_last_expression_type(body);
-                    if body_type != *declared_type {
+                // Os tipos dos campos são verificados antes de registrar a struct,
+                // então ela não pode conter a si mesma
+                let mut field_names = HashSet::new();
+                for field in fields {
+                    if !field_names.insert(&field.identifier) {
                         self.report_error(format!(
-                            "Tipo de retorno incompatível: esperado {:?}, encontrado {:?}",
-                            declared_type, body_type
+                            "Campo duplicado em {}: {}",
+                            name, field.identifier
                         ));
                     }
+                    self.check_type_exists(&field.r#type);
                 }
 
-                self.env = old_env;
+                self.env
+                    .borrow_mut()
+                    .structs
+                    .insert(name.clone(), fields.clone());
                 Ok(())
             }
+            Declaration::Enum(name, variants) => {
+                if self.env.borrow().enums.contains_key(name) {
+                    self.report_error(format!("Enum já declarado: {}", name));
+                } else if self.env.borrow().structs.contains_key(name) {
+                    self.report_error(format!("Tipo já declarado: {}", name));
+                }
+
+                // Registrado antes de verificar os valores das variantes, então um enum
+                // pode ser recursivo: a lista dá a indireção que falta à struct
+                self.env
+                    .borrow_mut()
+                    .enums
+                    .insert(name.clone(), variants.clone());
 
+                let mut variant_names = HashSet::new();
+                for variant in variants {
+                    if !variant_names.insert(&variant.identifier) {
+                        self.report_error(format!(
+                            "Variante duplicada em {}: {}",
+                            name, variant.identifier
+                        ));
+                    }
+                    for payload in &variant.payload {
+                        self.check_type_exists(payload);
+                    }
+                }
+                Ok(())
+            }
             Declaration::Compound(d1, d2) => {
                 self.check_declaration(d1)?;
                 self.check_declaration(d2)?;
 
                 Ok(())
             }
+            Declaration::Located(span, decl) => {
+                self.at(*span, |analyzer| analyzer.check_declaration(decl))
+            }
+            Declaration::Documented(_, decl) => self.check_declaration(decl),
+            // Erro de sintaxe já reportado pelo parser
+            Declaration::Error(_) => Ok(()),
         }
     }
 
-    pub fn check_expression(&mut self, expr: &Expression) -> Result<Type, Vec<String>> {
+    pub fn check_expression(&mut self, expr: &Expression) -> Result<Type, Vec<Diagnostic>> {
         match expr {
             Expression::ConcreteValue(cv) => match cv {
                 ConcreteValue::Value(Value::Int(_)) => Ok(Type::Int),
                 ConcreteValue::Value(Value::Str(_)) => Ok(Type::Str),
                 ConcreteValue::Value(Value::Bool(_)) => Ok(Type::Bool),
                 ConcreteValue::Value(Value::Unit) => Ok(Type::Unit),
+                ConcreteValue::Value(Value::Struct(name, _) | Value::Enum(name, _, _)) => {
+                    Ok(Type::Named(name.clone()))
+                }
+                // Listas, tuplas e options são sempre literais, com os elementos verificados
+                ConcreteValue::Value(
+                    Value::List(_) | Value::Tuple(_) | Value::Option(_) | Value::Closure(_),
+                ) => {
+                    Err(vec![self.error(
+                        "Valor sem tipo conhecido: use um literal de lista ou tupla",
+                    )])
+                }
             },
             Expression::Identifier(var) => {
-                let var_info = self
-                    .env
-                    .borrow()
-                    .lookup_variable(var)
-                    .ok_or_else(|| vec![format!("Variável não declarada: {}", var)])?;
+                // Um procedimento declarado também pode ser usado como valor
+                let binding = self.env.borrow().lookup_binding(var);
+                let var_info = match binding {
+                    Some(Binding::Variable(info)) => {
+                        self.note_capture(var);
+                        info
+                    }
+                    Some(Binding::Procedure(params, return_type)) => {
+                        return Ok(procedure_type(&params, return_type.as_ref()));
+                    }
+                    None => {
+                        return Err(vec![self.error(format!("Variável não declarada: {}", var))]);
+                    }
+                };
 
                 if var_info.moved {
                     self.report_error(format!("Uso de variável movida: {}", var));
+                } else if let Some(field) = var_info.moved_fields.iter().min() {
+                    self.report_error(format!(
+                        "Uso de variável parcialmente movida: {} (campo '{}' já foi movido)",
+                        var, field
+                    ));
                 }
 
                 Ok(var_info.type_.clone())
             }
+            Expression::FieldAccess(object, field) => match expr.field_path() {
+                // Campo de uma variável: só o caminho lido precisa estar disponível
+                Some((var, path)) => {
+                    let var_info = self.lookup_variable(&var).ok_or_else(|| {
+                        vec![self.error(format!("Variável não declarada: {}", var))]
+                    })?;
+
+                    let path = path.join(".");
+                    if var_info.moved {
+                        self.report_error(format!("Uso de variável movida: {}", var));
+                    } else if let Some(moved) = moved_overlap(&var_info, &path) {
+                        self.report_error(format!("Uso de campo movido: {}.{}", var, moved));
+                    }
+
+                    let mut field_type = var_info.type_;
+                    for field in path.split('.') {
+                        field_type = self.field_type(&field_type, field)?;
+                    }
+                    Ok(field_type)
+                }
+                None => {
+                    let object_type = self.check_expression(object)?;
+                    self.field_type(&object_type, field)
+                }
+            },
+            Expression::StructLiteral(name, fields) => self.check_struct_literal(name, fields),
+            Expression::EnumVariant(name, variant, values) => {
+                self.check_enum_variant(name, variant, values)
+            }
+            Expression::Match(value, arms) => self.check_match_expression(value, arms, None),
+            Expression::IfLet(pattern, value, then_expr, else_expr) => {
+                self.check_if_let_expression(pattern, value, then_expr, else_expr, None)
+            }
+            Expression::Some(value) => Ok(Type::Option(Box::new(self.check_expression(value)?))),
+            Expression::None => Err(vec![self.error(
+                "Tipo de none não pode ser inferido: declare o tipo, como em var x: option<int> = none",
+            )]),
+            Expression::ListLiteral(elements) => self.check_list_literal(elements, None),
+            Expression::TupleLiteral(components) => {
+                let mut types = Vec::new();
+                for component in components {
+                    types.push(self.check_expression(component)?);
+                }
+                Ok(Type::Tuple(types))
+            }
+            Expression::Index(list, index) => {
+                let list_type = self.check_expression(list)?;
+                self.check_index(index)?;
+                match list_type {
+                    Type::List(element_type) => Ok(*element_type),
+                    other => {
+                        Err(vec![self.error(format!(
+                            "Indexação de algo que não é lista: {}",
+                            other
+                        ))])
+                    }
+                }
+            }
             Expression::UnaryExp(op, expr) => {
                 let expr_type = self.check_expression(expr)?;
                 match op {
@@ -341,36 +1538,62 @@ impl SemanticAnalyzer {
                     }
                     UnaryOperator::Not => {
                         if expr_type != Type::Bool {
-                            self.report_error(format!("Negação lógica aplicada a algo que não é booleano: {:?}", expr_type));
+                            self.report_error(format!(
+                                "Negação lógica aplicada a algo que não é booleano: {}",
+                                expr_type
+                            ));
                         }
 
                         Ok(Type::Bool)
                     }
+                    UnaryOperator::BitNot => {
+                        if expr_type != Type::Int {
+                            self.report_error("Complemento bit a bit aplicado a um não-inteiro");
+                        }
+
+                        Ok(Type::Int)
+                    }
                     UnaryOperator::Length => {
-                        if expr_type != Type::Str {
-                            self.report_error(format!("Length aplicado a algo que não é string: {:?}", expr_type));
+                        if !matches!(expr_type, Type::Str | Type::List(_)) {
+                            self.report_error(format!(
+                                "Length aplicado a algo que não é string nem lista: {}",
+                                expr_type
+                            ));
                         }
 
                         Ok(Type::Int)
                     }
                 }
             }
+            Expression::IfElse(cond, then_expr, else_expr) => {
+                self.check_if_expression(cond, then_expr, else_expr, None)
+            }
             Expression::BinaryExp(op, left, right) => {
                 let left_type = self.check_expression(left)?;
                 let right_type = self.check_expression(right)?;
 
                 match op {
-                    BinaryOperator::Add | BinaryOperator::Sub => {
+                    BinaryOperator::Add
+                    | BinaryOperator::Sub
+                    | BinaryOperator::Mul
+                    | BinaryOperator::Div
+                    | BinaryOperator::Mod
+                    | BinaryOperator::Pow
+                    | BinaryOperator::BitAnd
+                    | BinaryOperator::BitOr
+                    | BinaryOperator::BitXor
+                    | BinaryOperator::Shl
+                    | BinaryOperator::Shr => {
                         if left_type != Type::Int || right_type != Type::Int {
-                            self.report_error(format!("Operações aritméticas esperam 2 inteiros, porém foi passado {:?} e {:?}", left_type, right_type));
+                            self.report_error(format!("Operações aritméticas esperam 2 inteiros, porém foi passado {} e {}", left_type, right_type));
                         }
 
                         Ok(Type::Int)
                     }
-                    BinaryOperator::Equal => {
+                    BinaryOperator::Equal | BinaryOperator::NotEqual => {
                         if left_type != right_type {
                             self.report_error(format!(
-                                "Não pode comprar tipos diferentes: {:?} e {:?}",
+                                "Não pode comprar tipos diferentes: {} e {}",
                                 left_type, right_type
                             ));
                         }
@@ -403,79 +1626,310 @@ impl SemanticAnalyzer {
                 }
             }
             Expression::CallProcedure(call) => {
-                let proc_info = self
-                    .env
-                    .borrow()
-                    .lookup_procedure(&call.id)
-                    .ok_or_else(|| vec![format!("Procedimento não declarado: {}", call.id)])?;
-
-                // Verificar número de argumentos
-                if call.args.len() != proc_info.0.len() {
-                    self.report_error(format!(
-                        "Número incorreto de argumentos para {}: esperado {}, obtido {}",
-                        call.id,
-                        proc_info.0.len(),
-                        call.args.len()
-                    ));
+                // Um procedimento declarado ou uma variável que guarda um procedimento,
+                // o que estiver no escopo mais próximo
+                let binding = self.env.borrow().lookup_binding(&call.id);
+                let (params, return_type) = match binding {
+                    Some(Binding::Procedure(params, return_type)) => (
+                        params.into_iter().map(|param| param.r#type).collect(),
+                        return_type.unwrap_or(Type::Unit),
+                    ),
+                    Some(Binding::Variable(_)) => {
+                        let callee = Expression::Identifier(call.id.clone());
+                        match self.check_expression(&callee)? {
+                            Type::Proc(params, return_type) => (params, *return_type),
+                            other => {
+                                return Err(vec![self.error(format!(
+                                    "{} não é um procedimento: {}",
+                                    call.id, other
+                                ))]);
+                            }
+                        }
+                    }
+                    // Um procedimento declarado com o mesmo nome esconde o pré-definido
+                    None if matches!(call.id.as_str(), "push" | "pop") => {
+                        return self.check_builtin(call);
+                    }
+                    None => {
+                        return Err(vec![
+                            self.error(format!("Procedimento não declarado: {}", call.id)),
+                        ]);
+                    }
+                };
+
+                self.check_arguments(&call.id, &params, &call.args)?;
+                Ok(return_type)
+            }
+            Expression::Call(callee, args) => match self.check_expression(callee)? {
+                Type::Proc(params, return_type) => {
+                    let name = Type::Proc(params.clone(), return_type.clone()).to_string();
+                    self.check_arguments(&name, &params, args)?;
+                    Ok(*return_type)
                 }
+                other => Err(vec![self.error(format!(
+                    "Chamada de algo que não é procedimento: {}",
+                    other
+                ))]),
+            },
+            Expression::Lambda(params, return_type, body) => {
+                self.check_procedure(None, params, return_type.as_ref(), body)?;
+                Ok(procedure_type(params, return_type.as_ref()))
+            }
+            Expression::Located(span, expr) => {
+                self.at(*span, |analyzer| analyzer.check_expression(expr))
+            }
+        }
+    }
 
-                // Verificar tipos e coletar variáveis para mover
-                let mut vars_to_move = Vec::new();
-                for (i, (arg, param)) in call.args.iter().zip(proc_info.0.iter()).enumerate() {
-                    let arg_type = self.check_expression(arg)?;
+    // Verifica os argumentos de uma chamada contra os tipos dos parâmetros
+    fn check_arguments(
+        &mut self,
+        name: &str,
+        params: &[Type],
+        args: &[Expression],
+    ) -> Result<(), Vec<Diagnostic>> {
+        if args.len() != params.len() {
+            self.report_error(format!(
+                "Número incorreto de argumentos para {}: esperado {}, obtido {}",
+                name,
+                params.len(),
+                args.len()
+            ));
+        }
 
-                    // Verificar compatibilidade de tipos
-                    if arg_type != param.r#type {
-                        self.report_error(format!(
-                            "Tipo inválido para argumento {} em {}: esperado {:?}, obtido {:?}",
-                            i + 1,
-                            call.id,
-                            param.r#type,
-                            arg_type
-                        ));
-                    }
+        // Verificar tipos, da esquerda para a direita. Um identificador passado
+        // diretamente é movido para o procedimento; uma expressão composta só lê
+        // as suas variáveis e passa um valor temporário
+        for (i, (arg, param)) in args.iter().zip(params).enumerate() {
+            let arg_type = self.check_expression_as(arg, param)?;
+
+            if arg_type != *param {
+                self.report_error(format!(
+                    "Tipo inválido para argumento {} em {}: esperado {}, obtido {}",
+                    i + 1,
+                    name,
+                    param,
+                    arg_type
+                ));
+            }
 
-                    // Coletar identificadores para mover após verificação
-                    if let Expression::Identifier(var_name) = arg {
-                        vars_to_move.push(var_name.to_string());
-                    }
+            // Movido logo após o seu argumento: os argumentos seguintes não podem usá-lo.
+            // Um valor Copy, como um procedimento, continua disponível
+            if let Some((var_name, path)) = arg.field_path()
+                && !is_copy(&arg_type)
+            {
+                if path.is_empty() {
+                    self.mark_variable_as_moved(&var_name);
+                } else {
+                    self.mark_field_as_moved(&var_name, &path);
                 }
+            }
+        }
 
-                // Processar movimentação das variáveis
-                for var_name in vars_to_move {
-                    // Verificar com borrow imutável primeiro
-                    let exists_and_not_moved = {
-                        let env = self.env.borrow();
-                        env.variables.get(&var_name).map_or(false, |v| !v.moved)
-                    };
-
-                    if !exists_and_not_moved {
-                        // Verificar se existe para mensagem de erro precisa
-                        let exists = self.env.borrow().variables.contains_key(&var_name);
-                        if !exists {
-                            self.report_error(format!("Variável '{}' não declarada", var_name));
-                        } else {
-                            self.report_error(format!("Variável '{}' já foi movida", var_name));
-                        }
-                    } else {
-                        // Marcar como movida com borrow mutável
-                        let mut env = self.env.borrow_mut();
-                        if let Some(var_info) = env.variables.get_mut(&var_name) {
-                            var_info.moved = true;
-                        }
-                    }
-                }
+        Ok(())
+    }
+
+    // Verifica os dois ramos de um if a partir do mesmo estado: o que um ramo move
+    // continua disponível no outro, e depois do if fica movido se algum ramo o moveu
+    fn check_branches<T, E>(
+        &mut self,
+        then_branch: impl FnOnce(&mut Self) -> Result<T, Vec<Diagnostic>>,
+        else_branch: impl FnOnce(&mut Self) -> Result<E, Vec<Diagnostic>>,
+    ) -> Result<(T, E), Vec<Diagnostic>> {
+        let before = self.moved_state();
+        let then_result = then_branch(self)?;
+        let after_then = self.moved_state();
 
-                // Retornar tipo do procedimento
-                match &proc_info.1 {
-                    Some(t) => Ok(t.clone()),
-                    None => Ok(Type::Unit),
+        self.set_moved_state(&before);
+        let else_result = else_branch(self)?;
+
+        let mut after = self.moved_state();
+        merge_moved_state(&mut after, after_then);
+        self.set_moved_state(&after);
+
+        Ok((then_result, else_result))
+    }
+
+    // Quais variáveis e campos estão movidos, em cada escopo do atual até o global
+    fn moved_state(&self) -> Vec<HashMap<String, VariableInfo>> {
+        self.scopes()
+            .iter()
+            .map(|scope| scope.borrow().variables.clone())
+            .collect()
+    }
+
+    fn set_moved_state(&self, state: &[HashMap<String, VariableInfo>]) {
+        for (scope, saved) in self.scopes().iter().zip(state) {
+            for (name, info) in scope.borrow_mut().variables.iter_mut() {
+                if let Some(saved) = saved.get(name) {
+                    info.moved = saved.moved;
+                    info.moved_fields = saved.moved_fields.clone();
                 }
             }
         }
     }
 
+    // Uma variável nova não pode ter o nome de outra variável ou de um procedimento
+    // do mesmo escopo
+    fn check_new_variable(&mut self, name: &str) {
+        let (variable, procedure) = {
+            let env = self.env.borrow();
+            (
+                env.variables.contains_key(name),
+                env.procedures.contains_key(name),
+            )
+        };
+        if variable {
+            self.report_error(format!("Variável já declarada: {}", name));
+        } else if procedure {
+            self.report_error(format!(
+                "Nome já usado por um procedimento neste escopo: {}",
+                name
+            ));
+        }
+    }
+
+    // Uma variável capturada por um procedimento não pode ser movida, nem uma de fora
+    // do loop mais interno no corpo dele
+    fn check_move(&mut self, var: &str) {
+        let Some(scope) = self.declaring_scope(var) else {
+            return;
+        };
+        let captured_by = scope.borrow().captured.get(var).cloned();
+        if let Some(by) = captured_by {
+            self.report_error(format!(
+                "Variável '{}' não pode ser movida: foi capturada {}",
+                var, by
+            ));
+        } else if let Some(loop_scope) = &self.loop_scope
+            && encloses(&scope, loop_scope)
+        {
+            self.report_error(format!(
+                "Variável '{}' declarada fora do loop não pode ser movida dentro dele",
+                var
+            ));
+        }
+    }
+
+    // Busca uma variável registrando o seu uso pelo procedimento sendo verificado
+    fn lookup_variable(&mut self, var: &str) -> Option<VariableInfo> {
+        self.note_capture(var);
+        self.env.borrow().lookup_variable(var)
+    }
+
+    // Dentro de um procedimento, uma variável declarada fora dele é capturada
+    fn note_capture(&mut self, var: &str) {
+        if let Some(closure_scope) = &self.closure_scope
+            && let Some(scope) = self.declaring_scope(var)
+            && encloses(&scope, closure_scope)
+        {
+            self.captures.push((scope, var.to_string()));
+        }
+    }
+
+    // Escopo em que a variável visível com esse nome foi declarada
+    fn declaring_scope(&self, var: &str) -> Option<Rc<RefCell<Environment>>> {
+        self.scopes()
+            .into_iter()
+            .find(|scope| scope.borrow().variables.contains_key(var))
+    }
+
+    fn scopes(&self) -> Vec<Rc<RefCell<Environment>>> {
+        let mut scopes = vec![self.env.clone()];
+        let mut current = self.env.borrow().parent.clone();
+        while let Some(scope) = current {
+            current = scope.borrow().parent.clone();
+            scopes.push(scope);
+        }
+        scopes
+    }
+
     pub fn report_error(&mut self, msg: impl Into<String>) {
-        self.errors.push(msg.into());
+        let error = self.error(msg);
+        self.errors.push(error);
+    }
+
+    // Cria um erro na posição do nó sendo verificado
+    pub fn error(&self, msg: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(msg, self.span)
+    }
+
+    // Verifica um nó registrando a sua posição para as mensagens de erro
+    fn at<T>(&mut self, span: Span, check: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = self.span.replace(span);
+        let result = check(self);
+        self.span = previous;
+        result
+    }
+}
+
+// Indica se todo caminho do comando termina em um return
+fn always_returns(cmd: &Command) -> bool {
+    match cmd.node() {
+        Command::Return(_) => true,
+        Command::Sequence(first, second) => always_returns(first) || always_returns(second),
+        Command::IfElse(_, then_cmd, Some(else_cmd)) => {
+            always_returns(then_cmd) && always_returns(else_cmd)
+        }
+        Command::DeclarationBlock(_, body) => always_returns(body),
+        Command::Match(_, arms) => arms.iter().all(|(_, body)| always_returns(body)),
+        _ => false,
+    }
+}
+
+// Campo movido que impede ler o caminho: o próprio campo, um campo que o
+// contém ou um campo dentro dele
+fn moved_overlap<'a>(info: &'a VariableInfo, path: &str) -> Option<&'a String> {
+    info.moved_fields.iter().find(|moved| {
+        *moved == path
+            || path.starts_with(&format!("{}.", moved))
+            || moved.starts_with(&format!("{}.", path))
+    })
+}
+
+// Tipo de um procedimento como valor
+fn procedure_type(params: &[ProcedureParameter], return_type: Option<&Type>) -> Type {
+    Type::Proc(
+        params.iter().map(|param| param.r#type.clone()).collect(),
+        Box::new(return_type.cloned().unwrap_or(Type::Unit)),
+    )
+}
+
+// Valores que são copiados mesmo quando movidos; uma tupla é Copy se todos os componentes são
+fn is_copy(t: &Type) -> bool {
+    match t {
+        Type::Int | Type::Bool | Type::Unit => true,
+        Type::Tuple(components) => components.iter().all(is_copy),
+        Type::Option(inner) => is_copy(inner),
+        // O procedimento guarda só uma referência ao ambiente em que foi criado
+        Type::Proc(..) => true,
+        _ => false,
+    }
+}
+
+// Se outer é o escopo inner ou um dos que o envolvem
+fn encloses(outer: &Rc<RefCell<Environment>>, inner: &Rc<RefCell<Environment>>) -> bool {
+    let mut current = Some(inner.clone());
+    while let Some(scope) = current {
+        if Rc::ptr_eq(&scope, outer) {
+            return true;
+        }
+        current = scope.borrow().parent.clone();
+    }
+    false
+}
+
+// Depois de caminhos alternativos, fica movido o que algum deles moveu
+fn merge_moved_state(
+    state: &mut [HashMap<String, VariableInfo>],
+    other: Vec<HashMap<String, VariableInfo>>,
+) {
+    for (scope, other_scope) in state.iter_mut().zip(other) {
+        for (name, other_info) in other_scope {
+            if let Some(info) = scope.get_mut(&name) {
+                info.moved |= other_info.moved;
+                info.moved_fields.extend(other_info.moved_fields);
+            }
+        }
     }
 }
@@ -5,11 +5,20 @@ mod tests {
     #[test]
     fn test_valid_move() {
         let program = Program::Command(Command::DeclarationBlock(
-            vec![Declaration::Variable(
-                "a".to_string(),
-                Expression::ConcreteValue(ConcreteValue::Value(Value::Int(5))),
-                false,
-            )],
+            vec![
+                Declaration::Variable(
+                    "a".to_string(),
+                    None,
+                    Expression::ConcreteValue(ConcreteValue::Value(Value::Int(5))),
+                    false,
+                ),
+                Declaration::Variable(
+                    "b".to_string(),
+                    None,
+                    Expression::ConcreteValue(ConcreteValue::Value(Value::Int(0))),
+                    false,
+                ),
+            ],
             Box::new(Command::Assignment(
                 "b".to_string(),
                 Expression::Identifier("a".to_string()),
@@ -24,39 +33,60 @@ mod tests {
 
     #[test]
     fn test_invalid_move_twice() {
-        let program = Program::Command(Command::Sequence(
-            Box::new(Command::Assignment(
-                "b".to_string(),
-                Expression::Identifier("a".to_string()),
-                true,
-            )),
-            Box::new(Command::Assignment(
-                "c".to_string(),
-                Expression::Identifier("a".to_string()),
-                false,
+        let program = Program::Command(Command::DeclarationBlock(
+            ["a", "b", "c"]
+                .iter()
+                .map(|name| {
+                    Declaration::Variable(
+                        name.to_string(),
+                        None,
+                        Expression::ConcreteValue(ConcreteValue::Value(Value::Int(0))),
+                        false,
+                    )
+                })
+                .collect(),
+            Box::new(Command::Sequence(
+                Box::new(Command::Assignment(
+                    "b".to_string(),
+                    Expression::Identifier("a".to_string()),
+                    true,
+                )),
+                Box::new(Command::Assignment(
+                    "c".to_string(),
+                    Expression::Identifier("a".to_string()),
+                    false,
+                )),
             )),
         ));
 
         let mut analyzer = SemanticAnalyzer::new();
         let result = analyzer.check_program(&program);
         assert!(result.is_err());
-        assert!(analyzer.errors.iter().any(|e| e.contains("movida")));
+        assert!(analyzer.errors.iter().any(|e| e.message.contains("movida")));
     }
 
     #[test]
     fn test_type_mismatch() {
-        let program = Program::Command(Command::Assignment(
-            "x".to_string(),
-            Expression::BinaryExp(
-                BinaryOperator::Add,
-                Box::new(Expression::ConcreteValue(ConcreteValue::Value(Value::Int(
-                    5,
-                )))),
-                Box::new(Expression::ConcreteValue(ConcreteValue::Value(Value::Str(
-                    "texto".to_string(),
-                )))),
-            ),
-            false,
+        let program = Program::Command(Command::DeclarationBlock(
+            vec![Declaration::Variable(
+                "x".to_string(),
+                None,
+                Expression::ConcreteValue(ConcreteValue::Value(Value::Str(String::new()))),
+                false,
+            )],
+            Box::new(Command::Assignment(
+                "x".to_string(),
+                Expression::BinaryExp(
+                    BinaryOperator::Add,
+                    Box::new(Expression::ConcreteValue(ConcreteValue::Value(Value::Int(
+                        5,
+                    )))),
+                    Box::new(Expression::ConcreteValue(ConcreteValue::Value(Value::Str(
+                        "texto".to_string(),
+                    )))),
+                ),
+                false,
+            )),
         ));
 
         let mut analyzer = SemanticAnalyzer::new();
@@ -66,7 +96,7 @@ mod tests {
             analyzer
                 .errors
                 .iter()
-                .any(|e| e.contains("Tipo incompatível"))
+                .any(|e| e.message.contains("Tipo incompatível"))
         );
     }
 
@@ -106,7 +136,1784 @@ mod tests {
     //         analyzer
     //             .errors
     //             .iter()
-    //             .any(|e| e.contains("Tipo do parâmetro"))
+    //             .any(|e| e.message.contains("Tipo do parâmetro"))
     //     );
     // }
 }
+
+#[cfg(test)]
+mod repl_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_declarations_persist_between_inputs() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var x = 5").is_ok());
+        assert!(repl.eval("proc dobro(int a) int { a + a }").is_ok());
+        assert!(repl.eval("x := dobro(2)").is_ok());
+        assert_eq!(repl.eval("x").unwrap(), "4");
+        assert_eq!(repl.eval(":type x + 1").unwrap(), "int");
+    }
+
+    #[test]
+    fn test_run_raw_string_ending_in_backslash() {
+        // A barra no fim da string bruta não escapa as aspas: a chave
+        // seguinte está dentro de outra string e a entrada termina na linha
+        let mut repl = Repl::new();
+        let input = "var s = [r\"a\\\", \"{\"]\nvar t = r#\"b\\\"#\n";
+        repl.run(std::io::Cursor::new(input));
+        assert_eq!(repl.eval("s").unwrap(), r#"["a\\", "{"]"#);
+        assert_eq!(repl.eval("t").unwrap(), r#""b\\""#);
+    }
+
+    #[test]
+    fn test_failed_input_does_not_change_environment() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        assert!(repl.eval("var t = move s; var u = move s").is_err());
+        assert!(repl.eval("t").is_err());
+        assert_eq!(repl.eval("s").unwrap(), "\"oi\"");
+    }
+
+    #[test]
+    fn test_runtime_error_does_not_change_environment() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var c = 0").is_ok());
+        assert!(repl.eval("var conta = proc() { c := c + 1 }").is_ok());
+        assert!(repl.eval("var x = 1 / 0").is_err());
+        assert!(repl.eval("x").is_err());
+        assert!(repl.eval("var x = \"ok\"").is_ok());
+        assert_eq!(repl.eval("x").unwrap(), "\"ok\"");
+
+        // As alterações feitas antes do erro também são desfeitas
+        assert!(repl.eval("conta(); c := c / 0").is_err());
+        assert_eq!(repl.eval("c").unwrap(), "0");
+        assert!(repl.eval("conta()").is_ok());
+        assert_eq!(repl.eval("c").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_errors_point_to_the_input_that_caused_them() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var n = 0").is_ok());
+        assert!(repl.eval("proc f(int x) int {\n    10 / x\n}").is_ok());
+
+        let errors = repl.eval("write(f(n))").unwrap_err();
+        assert!(errors[0].contains("--> <repl>:2:5"));
+        assert!(errors[0].contains("2 |     10 / x\n  |     ^^^^^^"));
+
+        let errors = repl.eval("write(1 / n)").unwrap_err();
+        assert!(errors[0].contains("1 | write(1 / n)\n  |       ^^^^^"));
+        let errors = repl.eval("write(1 +)").unwrap_err();
+        assert!(errors[0].contains("--> <repl>:1:10"));
+    }
+
+    #[test]
+    fn test_reset_clears_environment() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var x = 1").is_ok());
+        assert!(repl.eval(":reset").is_ok());
+        assert!(repl.eval("x").is_err());
+    }
+}
+
+#[cfg(test)]
+mod span_tests {
+    use plp_2025::diagnostics::diagnostics::line_col;
+    use plp_2025::parsers::program_parser::parse_program;
+    use plp_2025::semantic::semantic::SemanticAnalyzer;
+
+    #[test]
+    fn test_semantic_error_points_to_source() {
+        let code = "{\n    var x = 1;\n    x := x + true\n}";
+        let program = parse_program(code).unwrap();
+
+        let mut analyzer = SemanticAnalyzer::new();
+        let errors = analyzer.check_program(&program).unwrap_err();
+        let span = errors[0].span.expect("erro sem posição");
+
+        assert_eq!(&code[span.start..span.end], "x + true");
+        assert_eq!(line_col(code, span.start), (3, 10));
+    }
+
+    #[test]
+    fn test_render_underlines_span() {
+        let code = "{\n    var x = 1;\n    y := 2\n}";
+        let program = parse_program(code).unwrap();
+
+        let mut analyzer = SemanticAnalyzer::new();
+        let errors = analyzer.check_program(&program).unwrap_err();
+        let rendered = errors[0].render(code, "teste.plp");
+
+        assert!(rendered.contains("--> teste.plp:3:5"));
+        assert!(rendered.contains("3 |     y := 2\n  |     ^^^^^^"));
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use plp_2025::ast::{Command, Program, Value};
+    use plp_2025::parsers::lexer::tokenize;
+    use plp_2025::parsers::program_parser::{parse_program, parse_program_with_errors};
+    use plp_2025::parsers::tokens::TokenKind;
+    use plp_2025::repl::repl::Repl;
+    use plp_2025::semantic::semantic::SemanticAnalyzer;
+
+    #[test]
+    fn test_trailing_input_is_rejected() {
+        let code = "x := 1;\ny := 2 @ 3;\nwrite(y)";
+        let error = &parse_program(code).unwrap_err()[0];
+
+        assert_eq!(error.offset, code.find('@').unwrap());
+        assert_eq!(error.to_string(), "unexpected '@' at 2:8");
+    }
+
+    #[test]
+    fn test_error_reports_expected_token_and_context() {
+        let code = "{\n    var i = 0;\n    while i < 3 od i := i + 1\n}";
+        let error = &parse_program(code).unwrap_err()[0];
+
+        assert_eq!(
+            error.to_string(),
+            "expected 'do' after while condition at 3:17"
+        );
+    }
+
+    #[test]
+    fn test_error_in_procedure_parameter_list() {
+        let code = "{ proc f(int a, 5) { a }; call f(1) }";
+        let error = &parse_program(code).unwrap_err()[0];
+
+        assert_eq!(
+            error.to_string(),
+            "expected type in procedure parameter list at 1:17"
+        );
+    }
+
+    #[test]
+    fn test_reports_every_syntax_error() {
+        let code = "{\n    var x = ;\n    x := 1 +;\n    write(x);\n    while x do skip od\n}";
+        let errors = parse_program(code).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "expected 'move' or expression in variable declaration at 2:13",
+                "expected expression after binary operator at 3:13",
+                "unexpected 'od' in block at 5:21",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_errors_in_match_arms() {
+        let code = "{ var x = 1; match x { 0 => skip, _ => { write(1 +); write(2) } } }";
+        let errors = parse_program(code).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "expected expression after binary operator at 1:51"
+        );
+    }
+
+    #[test]
+    fn test_reports_errors_in_if_let_branches() {
+        let code = "{ var o = some(1); if let some(y) = o then { write(y +); write(2) } else { write(3 *); write(4) } }";
+        let errors = parse_program(code).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "expected expression after binary operator at 1:55",
+                "expected expression after binary operator at 1:85",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_placeholders_are_skipped_by_analyzer() {
+        let code = "{ var x = 1; x := ; write(x) }";
+        let (program, errors) = parse_program_with_errors(code);
+        assert_eq!(errors.len(), 1);
+
+        let mut analyzer = SemanticAnalyzer::new();
+        assert!(analyzer.check_program(&program).is_ok());
+    }
+
+    #[test]
+    fn test_comments_are_accepted_as_whitespace() {
+        let code = "// contador\n{\n    var i = 0, /* separador */ var n = 3;\n    while i /* menor */ < n do // laço\n        i := i + /* um /* aninhado */ */ 1\n}\n// fim";
+        let program = parse_program(code).unwrap();
+
+        let mut analyzer = SemanticAnalyzer::new();
+        assert!(analyzer.check_program(&program).is_ok());
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let error = &parse_program("write(1) /* sem fim").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "expected '*/' to close block comment at 1:20"
+        );
+    }
+
+    #[test]
+    fn test_doc_comments_are_kept_on_procedures() {
+        let code = "{\n    /// Soma dois inteiros.\n    /// Devolve a soma.\n    proc soma(int a, int b) int { a + b };\n    var x = soma(1, 2);\n    write(x)\n}";
+        let program = parse_program(code).unwrap();
+
+        let Program::Command(cmd) = program;
+        let Command::DeclarationBlock(declarations, _) = cmd.node() else {
+            panic!("esperava um bloco de declarações");
+        };
+        assert_eq!(
+            declarations[0].doc(),
+            Some("Soma dois inteiros.\nDevolve a soma.")
+        );
+        assert_eq!(declarations[1].doc(), None);
+    }
+
+    #[test]
+    fn test_keyword_prefixes_are_identifiers() {
+        let code = "{\n    var iffy = 0, var done = 0, var variable = 3;\n    iffy := 1;\n    done := 2;\n    write(iffy + done + variable)\n}";
+        let program = parse_program(code).unwrap();
+
+        let mut analyzer = SemanticAnalyzer::new();
+        assert!(analyzer.check_program(&program).is_ok());
+    }
+
+    #[test]
+    fn test_keywords_are_reserved() {
+        let error = &parse_program("{ var while = 1; skip }").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "expected identifier in variable declaration at 1:7"
+        );
+    }
+
+    #[test]
+    fn test_tokens_have_positions() {
+        let tokens = tokenize("x := 10;\nwhile").unwrap();
+        let summary: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.kind.clone(), t.text, t.position.line, t.position.column))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (TokenKind::Identifier, "x", 1, 1),
+                (TokenKind::Symbol, ":=", 1, 3),
+                (TokenKind::Int, "10", 1, 6),
+                (TokenKind::Symbol, ";", 1, 8),
+                (TokenKind::Keyword, "while", 2, 1),
+                (TokenKind::Eof, "", 2, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let tokens = tokenize(r#""a\tb\n\"c\" \\ \u{e9}""#).unwrap();
+        assert_eq!(
+            tokens[0].kind,
+            TokenKind::Str("a\tb\n\"c\" \\ é".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_escape_is_reported() {
+        let error = tokenize("write(\"a\\qb\")").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unexpected '\\q' in string literal at 1:9"
+        );
+    }
+
+    #[test]
+    fn test_raw_strings_span_lines() {
+        let tokens = tokenize("r\"C:\\dir\" r#\"diz \"oi\"\nfim\"#").unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::Str("C:\\dir".to_string()));
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::Str("diz \"oi\"\nfim".to_string())
+        );
+    }
+
+    #[test]
+    fn test_string_values_are_displayed_escaped() {
+        let value = Value::Str("linha\n\"aspas\"\t\\".to_string());
+        assert_eq!(value.to_string(), r#""linha\n\"aspas\"\t\\""#);
+    }
+
+    #[test]
+    fn test_integer_literal_bases_and_separators() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("0xFF + 0b1010 + 0o17 + 1_000").unwrap(), "1280");
+        assert_eq!(
+            repl.eval("-9223372036854775808").unwrap(),
+            "-9223372036854775808"
+        );
+        assert_eq!(repl.eval("3 -1").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_integer_literal_out_of_range() {
+        let error = &parse_program("write(9223372036854775808)").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "integer literal out of range for int in write command at 1:7"
+        );
+        assert_eq!(error.span().end - error.span().start, 19);
+
+        let error = &parse_program("write(0b102)").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "invalid digit in binary literal in write command at 1:7"
+        );
+    }
+
+    #[test]
+    fn test_parenthesized_context_only_for_closing_paren() {
+        let error = &parse_program("write((9223372036854775808))").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "integer literal out of range for int in write command at 1:8"
+        );
+
+        let error = &parse_program("write((1 2))").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "expected ')' to close parenthesized expression at 1:10"
+        );
+    }
+
+    #[test]
+    fn test_unary_context_only_for_missing_operand() {
+        let error = &parse_program("write(-)").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "expected expression after unary operator at 1:8"
+        );
+
+        let error = &parse_program("write(-(1 2))").unwrap_err()[0];
+        assert_eq!(
+            error.to_string(),
+            "expected ')' to close parenthesized expression at 1:11"
+        );
+    }
+
+    #[test]
+    fn test_trailing_whitespace_is_accepted() {
+        assert!(parse_program("write(1)  \n\n").is_ok());
+    }
+}
+
+#[cfg(test)]
+mod operator_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_arithmetic_operators() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("7 * 6").unwrap(), "42");
+        assert_eq!(repl.eval("17 / 5").unwrap(), "3");
+        assert_eq!(repl.eval("17 % 5").unwrap(), "2");
+        assert_eq!(repl.eval("2 ** 10").unwrap(), "1024");
+        assert_eq!(repl.eval("-2 ** 2").unwrap(), "-4");
+        assert_eq!(repl.eval("(-2) ** 2").unwrap(), "4");
+        assert_eq!(repl.eval("{ var x = 2; -x ** 2 }").unwrap(), "-4");
+        assert_eq!(repl.eval("1 + 2 * 3").unwrap(), "7");
+        assert_eq!(repl.eval("3 != 4").unwrap(), "true");
+        assert_eq!(repl.eval("\"a\" != \"a\"").unwrap(), "false");
+    }
+
+    #[test]
+    fn test_gcd_without_repeated_subtraction() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var a = 1071, var b = 462, var t = 0").is_ok());
+        assert!(
+            repl.eval("while b != 0 do t := a % b; a := b; b := t")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("a").unwrap(), "21");
+    }
+
+    #[test]
+    fn test_division_by_zero_is_a_runtime_error() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var zero = 0").is_ok());
+
+        let errors = repl.eval("10 / zero").unwrap_err();
+        assert!(errors[0].contains("Divisão por zero"));
+        let errors = repl.eval("10 % zero").unwrap_err();
+        assert!(errors[0].contains("Resto de divisão por zero"));
+    }
+
+    #[test]
+    fn test_integer_overflow_is_a_runtime_error() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var menor = -9223372036854775808").is_ok());
+        let errors = repl.eval("-menor").unwrap_err();
+        assert!(errors[0].contains("Estouro de inteiro em -(-9223372036854775808)"));
+        let errors = repl.eval("-(-9223372036854775808)").unwrap_err();
+        assert!(errors[0].contains("Estouro de inteiro"));
+
+        assert!(repl.eval("var maior = 9223372036854775807").is_ok());
+        let errors = repl.eval("write(maior + 1)").unwrap_err();
+        assert!(errors[0].contains("Estouro de inteiro em 9223372036854775807 + 1"));
+        let errors = repl.eval("menor - 1").unwrap_err();
+        assert!(errors[0].contains("Estouro de inteiro em -9223372036854775808 - 1"));
+        assert_eq!(repl.eval("maior - 1 + 1").unwrap(), "9223372036854775807");
+    }
+}
+
+#[cfg(test)]
+mod precedence_tests {
+    use plp_2025::ast::{ConcreteValue, Expression};
+    use plp_2025::parsers::expression_parsers::parse_expression;
+    use plp_2025::parsers::lexer::tokenize;
+    use plp_2025::parsers::tokens::Tokens;
+
+    // Expressão com todos os parênteses explícitos
+    fn show(expr: &Expression) -> String {
+        match expr.node() {
+            Expression::BinaryExp(op, left, right) => {
+                format!("({} {:?} {})", show(left), op, show(right))
+            }
+            Expression::UnaryExp(op, expr) => format!("({:?} {})", op, show(expr)),
+            Expression::Identifier(name) => name.clone(),
+            Expression::ConcreteValue(ConcreteValue::Value(value)) => value.to_string(),
+            other => format!("{:?}", other),
+        }
+    }
+
+    fn parse(code: &str) -> String {
+        let tokens = tokenize(code).unwrap();
+        let (rest, expr) = parse_expression(Tokens::new(&tokens)).unwrap();
+        assert!(rest.is_empty(), "sobrou entrada em {:?}", code);
+        show(&expr)
+    }
+
+    #[test]
+    fn test_left_associative_operators() {
+        assert_eq!(parse("a - b - c"), "((a Sub b) Sub c)");
+        assert_eq!(parse("a / b * c"), "((a Div b) Mul c)");
+    }
+
+    #[test]
+    fn test_right_associative_operators() {
+        assert_eq!(parse("a ** b ** c"), "(a Pow (b Pow c))");
+        assert_eq!(parse("a ++ b ++ c"), "(a Concat (b Concat c))");
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        assert_eq!(parse("not a == b"), "(Not (a Equal b))");
+        assert_eq!(parse("not a and b"), "((Not a) And b)");
+        assert_eq!(parse("-x ++ y"), "((Neg x) Concat y)");
+        assert_eq!(parse("-x ** 2"), "(Neg (x Pow 2))");
+        assert_eq!(parse("-x * 2"), "((Neg x) Mul 2)");
+        assert_eq!(parse("2 ** -x"), "(2 Pow (Neg x))");
+        assert_eq!(parse("~x ** 2"), "(BitNot (x Pow 2))");
+    }
+
+    #[test]
+    fn test_mixed_precedence() {
+        assert_eq!(parse("a or b and c"), "(a Or (b And c))");
+        assert_eq!(parse("a and b or c and d"), "((a And b) Or (c And d))");
+        assert_eq!(
+            parse("a < b + c * d and e"),
+            "((a Less (b Add (c Mul d))) And e)"
+        );
+        assert_eq!(parse("a + b ++ c"), "((a Add b) Concat c)");
+    }
+}
+
+#[cfg(test)]
+mod call_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_expressions_as_arguments() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("proc soma(int a, int b) int { a + b }").is_ok());
+        assert!(repl.eval("proc nega(bool b) bool { not b }").is_ok());
+        assert!(repl.eval("var c = 4, var d = 10, var b = true").is_ok());
+        assert_eq!(repl.eval("soma(c + 1, d)").unwrap(), "15");
+        assert_eq!(repl.eval("soma(soma(1, 2) - 2, -c)").unwrap(), "-3");
+        assert_eq!(repl.eval("nega(not b)").unwrap(), "true");
+    }
+
+    #[test]
+    fn test_compound_arguments_do_not_move() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc tamanho(string s) int { length(s) }")
+                .is_ok()
+        );
+        assert!(repl.eval("var s = \"ab\"").is_ok());
+        assert_eq!(repl.eval("tamanho(s ++ \"c\")").unwrap(), "3");
+        assert_eq!(repl.eval("s").unwrap(), "\"ab\"");
+
+        assert_eq!(repl.eval("tamanho(s)").unwrap(), "2");
+        let errors = repl.eval("s").unwrap_err();
+        assert!(errors[0].contains("movida"));
+    }
+
+    #[test]
+    fn test_argument_moved_before_later_arguments() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc junta(string a, string b) string { a ++ b }")
+                .is_ok()
+        );
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        let errors = repl.eval("junta(s, s ++ \"!\")").unwrap_err();
+        assert!(errors[0].contains("movida"));
+        assert_eq!(repl.eval("junta(s ++ \"!\", s)").unwrap(), "\"oi!oi\"");
+    }
+
+    #[test]
+    fn test_move_argument_from_outer_scope() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("proc id(string a) string { a }").is_ok());
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        assert!(repl.eval("{ var t = id(s); write(t) }").is_ok());
+        assert!(repl.eval("s").unwrap_err()[0].contains("movida"));
+    }
+}
+
+#[cfg(test)]
+mod bitwise_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_bitwise_operators() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("0b1100 & 0b1010").unwrap(), "8");
+        assert_eq!(repl.eval("0b1100 | 0b1010").unwrap(), "14");
+        assert_eq!(repl.eval("0b1100 ^ 0b1010").unwrap(), "6");
+        assert_eq!(repl.eval("~0").unwrap(), "-1");
+        assert_eq!(repl.eval("1 << 10").unwrap(), "1024");
+        assert_eq!(repl.eval("-16 >> 2").unwrap(), "-4");
+        assert_eq!(repl.eval("1 | 2 ^ 3 & 4 << 1").unwrap(), "3");
+        assert_eq!(repl.eval(":type 0xFF & ~0x0F").unwrap(), "int");
+    }
+
+    #[test]
+    fn test_bitwise_operators_require_int() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("true & false").is_err());
+        assert!(repl.eval("~\"a\"").is_err());
+    }
+
+    #[test]
+    fn test_invalid_shift_amount() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var n = 64").is_ok());
+        let errors = repl.eval("1 << n").unwrap_err();
+        assert!(errors[0].contains("Deslocamento inválido: 64"));
+        let errors = repl.eval("1 >> -1").unwrap_err();
+        assert!(errors[0].contains("Deslocamento inválido: -1"));
+        assert_eq!(repl.eval("1 << 63").unwrap(), "-9223372036854775808");
+    }
+}
+
+#[cfg(test)]
+mod if_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_if_without_else() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var x = 1").is_ok());
+        assert!(repl.eval("if x > 0 then x := 10").is_ok());
+        assert!(repl.eval("if x > 100 then x := 0").is_ok());
+        assert_eq!(repl.eval("x").unwrap(), "10");
+    }
+
+    #[test]
+    fn test_else_if_chain() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var n = 0, var s = \"\"").is_ok());
+        let classify = "if n < 0 then s := \"negativo\" else if n == 0 then s := \"zero\" \
+                        else s := \"positivo\"";
+        for (n, expected) in [("-3", "negativo"), ("0", "zero"), ("8", "positivo")] {
+            assert!(repl.eval(&format!("n := {}", n)).is_ok());
+            assert!(repl.eval(classify).is_ok());
+            assert_eq!(repl.eval("s").unwrap(), format!("\"{}\"", expected));
+        }
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var a = 3, var b = 7").is_ok());
+        assert!(repl.eval("var m = if a > b then a else b").is_ok());
+        assert_eq!(repl.eval("m").unwrap(), "7");
+        assert_eq!(
+            repl.eval("1 + (if a == 3 then 10 else 20) * 2").unwrap(),
+            "21"
+        );
+        assert_eq!(
+            repl.eval(":type if true then \"s\" else \"t\"").unwrap(),
+            "string"
+        );
+    }
+
+    #[test]
+    fn test_if_expression_branch_types_must_agree() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("var v = if true then 1 else \"um\"").unwrap_err();
+        assert!(errors[0].contains("Ramos do If/Else com tipos diferentes"));
+        assert!(repl.eval("var w = if 1 then 1 else 2").is_err());
+    }
+
+    #[test]
+    fn test_if_expression_evaluates_only_the_chosen_branch() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var zero = 0").is_ok());
+        assert!(
+            repl.eval("var r = if zero == 0 then 0 else 10 / zero")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("r").unwrap(), "0");
+    }
+
+    #[test]
+    fn test_move_in_one_branch() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("var s = \"oi\", var t = \"\", var c = true")
+                .is_ok()
+        );
+        assert!(
+            repl.eval("if c then t := move s else write(length(s))")
+                .is_ok()
+        );
+        assert!(repl.eval("s").unwrap_err()[0].contains("movida"));
+    }
+}
+
+#[cfg(test)]
+mod loop_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_for_ranges() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var total = 0").is_ok());
+        assert!(repl.eval("for i in 0..5 do total := total + i").is_ok());
+        assert_eq!(repl.eval("total").unwrap(), "10");
+
+        assert!(
+            repl.eval("total := 0; for i in 1..=5 do total := total + i")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("total").unwrap(), "15");
+
+        assert!(
+            repl.eval("total := 0; for i in 0..10 step 3 do total := total + i")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("total").unwrap(), "18");
+
+        let down = "var s = \"\"; for i in 3..=1 step -1 do s := s ++ \"x\"";
+        assert!(
+            repl.eval(&format!("{{ {}; total := length(s) }}", down))
+                .is_ok()
+        );
+        assert_eq!(repl.eval("total").unwrap(), "3");
+    }
+
+    #[test]
+    fn test_for_variable_is_scoped_to_the_body() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("for i in 0..3 do skip").is_ok());
+        let errors = repl.eval("i").unwrap_err();
+        assert!(errors[0].contains("Variável não declarada: i"));
+    }
+
+    #[test]
+    fn test_zero_step_is_a_runtime_error() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var passo = 0").is_ok());
+        let errors = repl.eval("for i in 0..3 step passo do skip").unwrap_err();
+        assert!(errors[0].contains("Passo do for não pode ser zero"));
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var total = 0").is_ok());
+        assert!(
+            repl.eval(
+                "for i in 0..100 do { var par = i % 2 == 0; \
+                 if i > 6 then break else if par then continue else total := total + i }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("total").unwrap(), "9");
+
+        assert!(repl.eval("var n = 0").is_ok());
+        assert!(
+            repl.eval("while true do { var x = 0; n := n + 1; if n == 4 then break }")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("n").unwrap(), "4");
+    }
+
+    #[test]
+    fn test_labeled_break_and_continue() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var pares = 0").is_ok());
+        assert!(
+            repl.eval(
+                "externo: for i in 0..10 do for j in 0..10 do \
+                 if j > i then continue externo else if i == 4 then break externo \
+                 else pares := pares + 1"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("pares").unwrap(), "10");
+    }
+
+    #[test]
+    fn test_break_outside_loop_is_rejected() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("break").unwrap_err();
+        assert!(errors[0].contains("break fora de um loop"));
+
+        let errors = repl.eval("while true do continue externo").unwrap_err();
+        assert!(errors[0].contains("nenhum loop com rótulo 'externo'"));
+
+        let errors = repl
+            .eval("while true do { proc p() { break }; skip }")
+            .unwrap_err();
+        assert!(errors[0].contains("break fora de um loop"));
+    }
+
+    #[test]
+    fn test_outer_variable_cannot_be_moved_in_loop_body() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        let errors = repl
+            .eval("for i in 0..3 do { var t = move s; write(t) }")
+            .unwrap_err();
+        assert!(
+            errors[0]
+                .contains("Variável 's' declarada fora do loop não pode ser movida dentro dele")
+        );
+
+        let errors = repl
+            .eval("while true do { var t = move s; break }")
+            .unwrap_err();
+        assert!(errors[0].contains("declarada fora do loop"));
+        assert_eq!(repl.eval("s").unwrap(), "\"oi\"");
+
+        // Variáveis do próprio corpo são novas a cada iteração
+        assert!(
+            repl.eval("for i in 0..2 do { var a = \"x\"; var b = move a; write(b) }")
+                .is_ok()
+        );
+    }
+}
+
+#[cfg(test)]
+mod return_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_early_return_from_nested_loops() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval(
+                "proc primeiro_divisor(int n) int { \
+                 for i in 2..n do { for j in 0..1 do if n % i == 0 then return i }; \
+                 return n }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("primeiro_divisor(91)").unwrap(), "7");
+        assert_eq!(repl.eval("primeiro_divisor(13)").unwrap(), "13");
+    }
+
+    #[test]
+    fn test_return_and_tail_expression() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval(
+                "proc sinal(int n) int { if n < 0 then { return -1 }; if n == 0 then { return 0 }; 1 }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("sinal(-5)").unwrap(), "-1");
+        assert_eq!(repl.eval("sinal(0)").unwrap(), "0");
+        assert_eq!(repl.eval("sinal(9)").unwrap(), "1");
+        assert!(
+            repl.eval("proc max(int a, int b) int { if a > b then return a else return b }")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("max(3, 8)").unwrap(), "8");
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let mut repl = Repl::new();
+        let errors = repl
+            .eval("proc f(int n) int { if n > 0 then return 1 }")
+            .unwrap_err();
+        assert!(errors[0].contains("missing return"));
+
+        let errors = repl
+            .eval("proc g(int n) int { while n > 0 do return n }")
+            .unwrap_err();
+        assert!(errors[0].contains("missing return"));
+    }
+
+    #[test]
+    fn test_return_type_and_placement() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("proc f() int { return \"um\" }").unwrap_err();
+        assert!(errors[0].contains("Tipo de retorno incompatível"));
+
+        let errors = repl.eval("return 1").unwrap_err();
+        assert!(errors[0].contains("return fora de um procedimento"));
+
+        assert!(repl.eval("var x = 0").is_ok());
+        assert!(
+            repl.eval("proc p(int n) { if n < 0 then { return }; x := n }")
+                .is_ok()
+        );
+        assert!(repl.eval("call p(-1); call p(4)").is_ok());
+        assert_eq!(repl.eval("x").unwrap(), "4");
+    }
+}
+
+#[cfg(test)]
+mod tail_expression_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_if_else_as_procedure_result() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc max(int a, int b) int { if a > b then a else b }")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("max(3, 8)").unwrap(), "8");
+        assert_eq!(repl.eval("max(9, 8)").unwrap(), "9");
+        assert_eq!(repl.eval(":type max(1, 2)").unwrap(), "int");
+    }
+
+    #[test]
+    fn test_block_as_procedure_result() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval(
+                "proc quadrado_mais_um(int n) int { { var q = n * n; if q > 100 then { 100 } else { q + 1 } } }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("quadrado_mais_um(3)").unwrap(), "10");
+        assert_eq!(repl.eval("quadrado_mais_um(30)").unwrap(), "100");
+    }
+
+    #[test]
+    fn test_branches_without_value_are_rejected() {
+        let mut repl = Repl::new();
+        let errors = repl
+            .eval("proc f(int n) int { if n > 0 then n else skip }")
+            .unwrap_err();
+        assert!(errors[0].contains("missing return"));
+
+        let errors = repl
+            .eval("proc g(int n) int { if n > 0 then n else \"n\" }")
+            .unwrap_err();
+        assert!(errors[0].contains("Ramos do If/Else com tipos diferentes"));
+    }
+
+    #[test]
+    fn test_tail_expression_is_evaluated_once() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var chamadas = 0").is_ok());
+        assert!(
+            repl.eval("proc conta() int { chamadas := chamadas + 1; chamadas }")
+                .is_ok()
+        );
+        assert!(repl.eval("proc usa() int { conta() }").is_ok());
+        assert_eq!(repl.eval("usa()").unwrap(), "1");
+        assert_eq!(repl.eval("chamadas").unwrap(), "1");
+    }
+
+    #[test]
+    fn test_procedure_without_return_type_discards_tail() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("proc f() { \"s\" }").is_ok());
+        assert!(repl.eval("var x = f()").is_ok());
+        assert_eq!(repl.eval(":type x").unwrap(), "unit");
+        assert_eq!(repl.eval("f()").unwrap(), "");
+        assert_eq!(repl.eval("x").unwrap(), "");
+    }
+
+    #[test]
+    fn test_repl_shows_block_and_if_values() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("{ var y = 2; y * 3 }").unwrap(), "6");
+        assert_eq!(
+            repl.eval("if 1 < 2 then \"sim\" else \"não\"").unwrap(),
+            "\"sim\""
+        );
+        assert_eq!(repl.eval("if 1 > 2 then 5").unwrap(), "");
+    }
+}
+
+#[cfg(test)]
+mod struct_tests {
+    use plp_2025::repl::repl::Repl;
+
+    fn repl_with_structs() -> Repl {
+        let mut repl = Repl::new();
+        assert!(repl.eval("struct Ponto { int x, int y }").is_ok());
+        assert!(repl.eval("struct Conta { string nome, int saldo }").is_ok());
+        repl
+    }
+
+    #[test]
+    fn test_literal_field_read_and_assignment() {
+        let mut repl = repl_with_structs();
+        assert!(repl.eval("var p = Ponto { y: 2, x: 1 }").is_ok());
+        assert_eq!(repl.eval("p").unwrap(), "Ponto { x: 1, y: 2 }");
+        assert_eq!(repl.eval("p.x + p.y").unwrap(), "3");
+        assert!(repl.eval("p.x := 5").is_ok());
+        assert_eq!(repl.eval("p.x").unwrap(), "5");
+        assert_eq!(repl.eval(":type p").unwrap(), "Ponto");
+    }
+
+    #[test]
+    fn test_struct_parameter_and_nested_fields() {
+        let mut repl = repl_with_structs();
+        assert!(
+            repl.eval("proc norma(Ponto p) int { p.x * p.x + p.y * p.y }")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("norma(Ponto { x: 3, y: 4 })").unwrap(), "25");
+
+        assert!(repl.eval("struct Segmento { Ponto a, Ponto b }").is_ok());
+        assert!(
+            repl.eval("var s = Segmento { a: Ponto { x: 0, y: 0 }, b: Ponto { x: 1, y: 1 } }")
+                .is_ok()
+        );
+        assert!(repl.eval("s.b.x := 7").is_ok());
+        assert_eq!(repl.eval("s.b.x").unwrap(), "7");
+    }
+
+    #[test]
+    fn test_moving_one_field() {
+        let mut repl = repl_with_structs();
+        assert!(
+            repl.eval("var c = Conta { nome: \"ana\", saldo: 10 }")
+                .is_ok()
+        );
+        assert!(repl.eval("var n = move c.nome").is_ok());
+        assert_eq!(repl.eval("c.saldo").unwrap(), "10");
+
+        let errors = repl.eval("write(c.nome)").unwrap_err();
+        assert!(errors[0].contains("Uso de campo movido: c.nome"));
+        let errors = repl.eval("var d = c").unwrap_err();
+        assert!(errors[0].contains("parcialmente movida"));
+
+        assert!(repl.eval("c.nome := \"bia\"").is_ok());
+        assert_eq!(
+            repl.eval("c").unwrap(),
+            "Conta { nome: \"bia\", saldo: 10 }"
+        );
+    }
+
+    #[test]
+    fn test_invalid_literals_and_fields() {
+        let mut repl = repl_with_structs();
+        let errors = repl.eval("var p = Ponto { x: 1 }").unwrap_err();
+        assert!(errors[0].contains("Campo 'y' faltando em Ponto"));
+
+        let errors = repl.eval("var p = Ponto { x: 1, y: 2, z: 3 }").unwrap_err();
+        assert!(errors[0].contains("Campo 'z' não existe em Ponto"));
+
+        let errors = repl.eval("var p = Ponto { x: 1, y: true }").unwrap_err();
+        assert!(
+            errors[0].contains("Tipo inválido para o campo y de Ponto: esperado int, obtido bool")
+        );
+
+        let errors = repl.eval("var q: Ponto = [1]").unwrap_err();
+        assert!(errors[0].contains("esperado Ponto, obtido [int]"));
+
+        assert!(repl.eval("var p = Ponto { x: 1, y: 2 }").is_ok());
+        let errors = repl.eval("p.z := 1").unwrap_err();
+        assert!(errors[0].contains("Campo 'z' não existe em Ponto"));
+
+        let errors = repl
+            .eval("struct Lista { int valor, Lista resto }")
+            .unwrap_err();
+        assert!(errors[0].contains("Tipo não declarado: Lista"));
+    }
+}
+
+#[cfg(test)]
+mod list_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_literal_indexing_and_length() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var xs = [10, 20, 30]").is_ok());
+        assert_eq!(repl.eval("xs").unwrap(), "[10, 20, 30]");
+        assert_eq!(repl.eval("xs[1] + xs[2]").unwrap(), "50");
+        assert_eq!(repl.eval("length(xs)").unwrap(), "3");
+        assert_eq!(repl.eval(":type xs").unwrap(), "[int]");
+        assert!(repl.eval("xs[0] := 7").is_ok());
+        assert_eq!(repl.eval("xs").unwrap(), "[7, 20, 30]");
+    }
+
+    #[test]
+    fn test_index_out_of_bounds_is_a_runtime_error() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var xs = [1, 2]").is_ok());
+        let errors = repl.eval("xs[2]").unwrap_err();
+        assert!(errors[0].contains("Índice fora dos limites: 2 (tamanho da lista: 2)"));
+        let errors = repl.eval("xs[-1] := 0").unwrap_err();
+        assert!(errors[0].contains("Índice fora dos limites: -1"));
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var xs: [int] = []").is_ok());
+        assert!(repl.eval("push(xs, 1); push(xs, 2); push(xs, 3)").is_ok());
+        assert_eq!(repl.eval("pop(xs)").unwrap(), "3");
+        assert_eq!(repl.eval("xs").unwrap(), "[1, 2]");
+        assert!(repl.eval("pop(xs); pop(xs)").is_ok());
+        let errors = repl.eval("pop(xs)").unwrap_err();
+        assert!(errors[0].contains("pop em lista vazia"));
+    }
+
+    #[test]
+    fn test_nested_lists_and_struct_fields() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var m = [[1, 2], [3, 4]]").is_ok());
+        assert!(repl.eval("m[1][0] := 9; push(m[0], 5)").is_ok());
+        assert_eq!(repl.eval("m").unwrap(), "[[1, 2, 5], [9, 4]]");
+
+        assert!(repl.eval("struct Turma { [string] alunos }").is_ok());
+        assert!(repl.eval("var t = Turma { alunos: [] }").is_ok());
+        assert!(repl.eval("push(t.alunos, \"ana\")").is_ok());
+        assert_eq!(repl.eval("t.alunos[0]").unwrap(), "\"ana\"");
+    }
+
+    #[test]
+    fn test_lists_move_like_strings() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval(
+                "proc soma([int] xs) int { { var s = 0; for i in 0..length(xs) do { s := s + xs[i] }; s } }"
+            )
+            .is_ok()
+        );
+        assert!(repl.eval("var xs = [1, 2, 3]").is_ok());
+        assert_eq!(repl.eval("soma(xs)").unwrap(), "6");
+        let errors = repl.eval("xs[0]").unwrap_err();
+        assert!(errors[0].contains("Uso de variável movida: xs"));
+
+        assert!(repl.eval("var ys = [4]; var zs = move ys").is_ok());
+        let errors = repl.eval("push(ys, 5)").unwrap_err();
+        assert!(errors[0].contains("Uso de variável movida: ys"));
+    }
+
+    #[test]
+    fn test_list_type_errors() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("var xs = []").unwrap_err();
+        assert!(errors[0].contains("Tipo da lista vazia não pode ser inferido"));
+
+        let errors = repl.eval("var xs = [1, \"dois\"]").unwrap_err();
+        assert!(errors[0].contains("Elementos da lista com tipos diferentes"));
+
+        assert!(repl.eval("var xs = [1]").is_ok());
+        let errors = repl.eval("xs[true]").unwrap_err();
+        assert!(errors[0].contains("Índice de lista deve ser int"));
+        let errors = repl.eval("push(xs, \"a\")").unwrap_err();
+        assert!(errors[0].contains("Tipo inválido para argumento 2 em push"));
+        let errors = repl.eval("var s = move xs[0]").unwrap_err();
+        assert!(errors[0].contains("Move só pode ser aplicado a identificadores ou campos"));
+    }
+}
+
+#[cfg(test)]
+mod tuple_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_procedure_returning_tuple() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc divmod(int a, int b) (int, int) { (a / b, a % b) }")
+                .is_ok()
+        );
+        assert!(repl.eval("var (q, r) = divmod(17, 5)").is_ok());
+        assert_eq!(repl.eval("q").unwrap(), "3");
+        assert_eq!(repl.eval("r").unwrap(), "2");
+        assert_eq!(repl.eval(":type divmod(1, 1)").unwrap(), "(int, int)");
+    }
+
+    #[test]
+    fn test_positional_access_and_assignment() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var t = (1, (\"a\", true))").is_ok());
+        assert_eq!(repl.eval("t.0").unwrap(), "1");
+        assert_eq!(repl.eval("t.1.0").unwrap(), "\"a\"");
+        assert!(repl.eval("t.1.1 := false").is_ok());
+        assert_eq!(repl.eval("t").unwrap(), "(1, (\"a\", false))");
+
+        let errors = repl.eval("t.2").unwrap_err();
+        assert!(errors[0].contains("não tem o componente 2"));
+    }
+
+    #[test]
+    fn test_moving_a_tuple_moves_only_non_copy_components() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var t = (1, \"um\")").is_ok());
+        assert!(repl.eval("var u = move t").is_ok());
+        assert_eq!(repl.eval("t.0").unwrap(), "1");
+        let errors = repl.eval("t.1").unwrap_err();
+        assert!(errors[0].contains("Uso de campo movido: t.1"));
+        let errors = repl.eval("t").unwrap_err();
+        assert!(errors[0].contains("parcialmente movida"));
+
+        // Uma tupla só de valores Copy continua inteira depois do move
+        assert!(repl.eval("var p = (1, 2); var p2 = move p").is_ok());
+        assert_eq!(repl.eval("p").unwrap(), "(1, 2)");
+    }
+
+    #[test]
+    fn test_destructuring_errors() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("var (a, b) = (1, 2, 3)").unwrap_err();
+        assert!(errors[0].contains("Desestruturação espera uma tupla com 2 componentes"));
+
+        let errors = repl.eval("var (a, a) = (1, 2)").unwrap_err();
+        assert!(errors[0].contains("Variável repetida na desestruturação: a"));
+
+        let errors = repl.eval("var (a, b) = 5").unwrap_err();
+        assert!(errors[0].contains("obtido int"));
+    }
+}
+
+#[cfg(test)]
+mod enum_tests {
+    use plp_2025::repl::repl::Repl;
+
+    const FORMA: &str = "enum Forma { Circulo(int), Retangulo(int, int), Vazio }";
+
+    #[test]
+    fn test_match_expression_on_payloads() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(FORMA).is_ok());
+        assert!(
+            repl.eval(
+                "proc area(Forma f) int { match f { Forma::Circulo(r) => 3 * r * r, Forma::Retangulo(l, a) => l * a, Forma::Vazio => 0 } }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("area(Forma::Circulo(2))").unwrap(), "12");
+        assert_eq!(repl.eval("area(Forma::Retangulo(3, 4))").unwrap(), "12");
+        assert_eq!(repl.eval("area(Forma::Vazio)").unwrap(), "0");
+        assert_eq!(
+            repl.eval("Forma::Retangulo(3, 4)").unwrap(),
+            "Forma::Retangulo(3, 4)"
+        );
+    }
+
+    #[test]
+    fn test_match_command_leaves_tail_value() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(FORMA).is_ok());
+        assert!(repl.eval("var f = Forma::Retangulo(2, 5)").is_ok());
+        assert_eq!(
+            repl.eval("match f { Forma::Retangulo(l, _) => { write(l); l + 1 }, _ => 0 }")
+                .unwrap(),
+            "3"
+        );
+        // O valor não é confundido com um literal de struct
+        assert_eq!(
+            repl.eval(
+                "match (f, true) { (Forma::Vazio, _) => 1, (_, true) => 2, (_, false) => 3 }"
+            )
+            .unwrap(),
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_non_exhaustive_match() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(FORMA).is_ok());
+        assert!(repl.eval("var f = Forma::Vazio").is_ok());
+        let errors = repl
+            .eval("match f { Forma::Circulo(_) => 1, Forma::Retangulo(_, _) => 2 }")
+            .unwrap_err();
+        assert!(errors[0].contains("match não exaustivo: o padrão Forma::Vazio não é coberto"));
+
+        let errors = repl.eval("match 3 { 1 => true, 2 => false }").unwrap_err();
+        assert!(errors[0].contains("o padrão _ não é coberto"));
+
+        let errors = repl.eval("match (1, true) { (_, true) => 1 }").unwrap_err();
+        assert!(errors[0].contains("o padrão (_, false) não é coberto"));
+    }
+
+    #[test]
+    fn test_unreachable_arm() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(FORMA).is_ok());
+        assert!(repl.eval("var f = Forma::Vazio").is_ok());
+        let errors = repl
+            .eval("match f { _ => 1, Forma::Vazio => 2 }")
+            .unwrap_err();
+        assert!(errors[0].contains("Braço do match inalcançável: Forma::Vazio"));
+
+        let errors = repl
+            .eval("match true { true => 1, false => 2, b => 3 }")
+            .unwrap_err();
+        assert!(errors[0].contains("Braço do match inalcançável: b"));
+    }
+
+    #[test]
+    fn test_pattern_errors() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(FORMA).is_ok());
+        assert!(repl.eval("var f = Forma::Vazio").is_ok());
+        let errors = repl
+            .eval("match f { Forma::Circulo(a, b) => 1, _ => 0 }")
+            .unwrap_err();
+        assert!(errors[0].contains("Número incorreto de valores no padrão"));
+
+        let errors = repl
+            .eval("match f { Forma::Quadrado => 1, _ => 0 }")
+            .unwrap_err();
+        assert!(errors[0].contains("Variante 'Quadrado' não existe em Forma"));
+
+        let errors = repl.eval("match f { 1 => 1, _ => 0 }").unwrap_err();
+        assert!(errors[0].contains("Padrão 1 não combina com o tipo Forma"));
+
+        let errors = repl.eval("Forma::Circulo(\"a\")").unwrap_err();
+        assert!(errors[0].contains("Tipo inválido para o valor 1 de Forma::Circulo"));
+    }
+
+    #[test]
+    fn test_binding_moves_non_copy_payload() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("enum Msg { Texto(string), Numero(int) }").is_ok());
+        assert!(repl.eval("var m = Msg::Numero(1)").is_ok());
+        assert_eq!(
+            repl.eval("match m { Msg::Numero(n) => n, Msg::Texto(_) => 0 }")
+                .unwrap(),
+            "1"
+        );
+        // Só valores Copy foram guardados: m continua disponível
+        assert!(repl.eval("m").is_ok());
+
+        assert!(repl.eval("var t = Msg::Texto(\"oi\")").is_ok());
+        assert_eq!(
+            repl.eval("match t { Msg::Texto(s) => s, Msg::Numero(_) => \"\" }")
+                .unwrap(),
+            "\"oi\""
+        );
+        let errors = repl.eval("t").unwrap_err();
+        assert!(errors[0].contains("Uso de variável movida"));
+    }
+
+    #[test]
+    fn test_recursive_enum() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("enum Arvore { Folha(int), No([Arvore]) }")
+                .is_ok()
+        );
+        assert!(
+            repl.eval(
+                "proc soma(Arvore a) int { match a { Arvore::Folha(v) => v, Arvore::No(filhos) => { var s = 0; for i in 0..length(filhos) do { s := s + soma(filhos[i]) }; s } } }"
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            repl.eval("soma(Arvore::No([Arvore::Folha(1), Arvore::No([Arvore::Folha(2), Arvore::Folha(3)])]))")
+                .unwrap(),
+            "6"
+        );
+
+        let errors = repl.eval("enum Arvore { Folha }").unwrap_err();
+        assert!(errors[0].contains("Enum já declarado: Arvore"));
+    }
+}
+
+#[cfg(test)]
+mod option_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_some_and_none_from_procedure() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval(
+                "proc primeiro([int] xs) option<int> { if length(xs) > 0 then some(xs[0]) else none }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("primeiro([4, 5])").unwrap(), "some(4)");
+        assert_eq!(repl.eval("primeiro([])").unwrap(), "none");
+        assert_eq!(
+            repl.eval("match primeiro([7]) { some(x) => x * 2, none => 0 }")
+                .unwrap(),
+            "14"
+        );
+    }
+
+    #[test]
+    fn test_if_let() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var o: option<int> = some(3)").is_ok());
+        assert_eq!(
+            repl.eval("if let some(x) = o then x + 1 else 0").unwrap(),
+            "4"
+        );
+        assert!(repl.eval("o := none").is_ok());
+        assert_eq!(
+            repl.eval("if let some(x) = o then x + 1 else 0").unwrap(),
+            "0"
+        );
+        assert_eq!(
+            repl.eval("if let none = o then { write(1); 10 } else 20")
+                .unwrap(),
+            "10"
+        );
+
+        // A variável do padrão só existe no ramo then
+        let errors = repl
+            .eval("if let some(x) = o then write(x) else write(x)")
+            .unwrap_err();
+        assert!(errors[0].contains("Variável não declarada: x"));
+    }
+
+    #[test]
+    fn test_none_needs_a_known_type() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("var o = none").unwrap_err();
+        assert!(errors[0].contains("Tipo de none não pode ser inferido"));
+
+        assert!(repl.eval("var o: option<string> = none").is_ok());
+        let errors = repl.eval("var p: option<int> = some(\"a\")").unwrap_err();
+        assert!(errors[0].contains("Tipo"));
+    }
+
+    #[test]
+    fn test_option_exhaustiveness() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var o: option<int> = some(1)").is_ok());
+        let errors = repl.eval("match o { some(x) => x }").unwrap_err();
+        assert!(errors[0].contains("o padrão none não é coberto"));
+
+        // option<option<int>>: os dois ">" juntos fecham os dois tipos
+        assert!(repl.eval("var n: option<option<int>> = some(none)").is_ok());
+        let errors = repl
+            .eval("match n { some(some(x)) => x, none => 0 }")
+            .unwrap_err();
+        assert!(errors[0].contains("o padrão some(none) não é coberto"));
+        assert_eq!(
+            repl.eval("match n { some(some(x)) => x, some(none) => -1, none => 0 }")
+                .unwrap(),
+            "-1"
+        );
+    }
+
+    #[test]
+    fn test_unwrapping_moves_non_copy_value() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var o: option<string> = some(\"oi\")").is_ok());
+        assert_eq!(
+            repl.eval("if let some(s) = o then s else \"\"").unwrap(),
+            "\"oi\""
+        );
+        let errors = repl.eval("o").unwrap_err();
+        assert!(errors[0].contains("Uso de variável movida"));
+
+        // option<int> é Copy
+        assert!(repl.eval("var i: option<int> = some(1)").is_ok());
+        assert!(repl.eval("if let some(x) = i then x else 0").is_ok());
+        assert_eq!(repl.eval("i").unwrap(), "some(1)");
+    }
+
+    #[test]
+    fn test_unknown_procedure_is_an_error() {
+        let mut repl = Repl::new();
+        let errors = repl.eval("nao_existe(1)").unwrap_err();
+        assert!(errors[0].contains("Procedimento não declarado: nao_existe"));
+    }
+}
+
+#[cfg(test)]
+mod lambda_tests {
+    use plp_2025::repl::repl::Repl;
+
+    const MAP: &str = "proc map([int] xs, proc(int) -> int f) [int] { { var ys: [int] = []; for i in 0..length(xs) do { push(ys, f(xs[i])) }; ys } }";
+
+    #[test]
+    fn test_lambda_in_variable() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var dobro = proc(int x) int { x * 2 }").is_ok());
+        assert_eq!(repl.eval("dobro(4)").unwrap(), "8");
+        assert_eq!(repl.eval("dobro").unwrap(), "<proc(int) -> int>");
+
+        let errors = repl.eval("dobro(\"a\")").unwrap_err();
+        assert!(errors[0].contains("Tipo inválido para argumento 1 em dobro"));
+    }
+
+    #[test]
+    fn test_procedures_as_arguments() {
+        let mut repl = Repl::new();
+        assert!(repl.eval(MAP).is_ok());
+        assert!(repl.eval("proc inc(int x) int { x + 1 }").is_ok());
+        assert!(repl.eval("var dobro = proc(int x) int { x * 2 }").is_ok());
+        assert_eq!(repl.eval("map([1, 2, 3], dobro)").unwrap(), "[2, 4, 6]");
+        // Procedimentos são Copy: passar como argumento não move
+        assert_eq!(repl.eval("map([1, 2], dobro)").unwrap(), "[2, 4]");
+        assert_eq!(repl.eval("map([1, 2], inc)").unwrap(), "[2, 3]");
+        assert_eq!(
+            repl.eval("map([1, 2], proc(int x) int { x * x })").unwrap(),
+            "[1, 4]"
+        );
+
+        let errors = repl
+            .eval("map([1], proc(int x) bool { x > 0 })")
+            .unwrap_err();
+        assert!(errors[0].contains("Tipo inválido para argumento 2 em map"));
+    }
+
+    #[test]
+    fn test_closure_captures_environment() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc somador(int n) proc(int) -> int { proc(int x) int { x + n } }")
+                .is_ok()
+        );
+        assert!(repl.eval("var soma5 = somador(5)").is_ok());
+        assert_eq!(repl.eval("soma5(1)").unwrap(), "6");
+        assert_eq!(repl.eval("somador(2)(3)").unwrap(), "5");
+
+        // O closure altera a variável capturada, não uma cópia
+        assert!(repl.eval("var c = 0").is_ok());
+        assert!(repl.eval("var conta = proc() { c := c + 1 }").is_ok());
+        assert!(repl.eval("conta(); conta()").is_ok());
+        assert_eq!(repl.eval("c").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_closures_created_in_loop_keep_their_iteration() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var fs: [proc() -> int] = []").is_ok());
+        assert!(
+            repl.eval("for i in 0..3 do { push(fs, proc() int { i }) }")
+                .is_ok()
+        );
+        assert_eq!(
+            repl.eval("[fs[0](), fs[1](), fs[2]()]").unwrap(),
+            "[0, 1, 2]"
+        );
+    }
+
+    #[test]
+    fn test_indirect_call_errors() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var n = 1").is_ok());
+        let errors = repl.eval("n(2)").unwrap_err();
+        assert!(errors[0].contains("n não é um procedimento: int"));
+
+        let errors = repl.eval("[1, 2](0)").unwrap_err();
+        assert!(errors[0].contains("Chamada de algo que não é procedimento: [int]"));
+
+        assert!(
+            repl.eval("var f: proc(int) -> int = proc(int x) int { x }")
+                .is_ok()
+        );
+        let errors = repl
+            .eval("var g: proc(int) -> bool = proc(int x) int { x }")
+            .unwrap_err();
+        assert!(errors[0].contains("proc(int) -> bool"));
+    }
+
+    #[test]
+    fn test_call_uses_nearest_binding() {
+        let mut repl = Repl::new();
+        assert_eq!(
+            repl.eval("{ proc f() int { 1 }; { var f = proc() int { 2 }; f() } }")
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(
+            repl.eval("{ var g = proc() int { 1 }; { proc g() int { 2 }; var h = g; g() + h() } }")
+                .unwrap(),
+            "4"
+        );
+
+        // A variável mais próxima decide também os tipos dos argumentos
+        assert_eq!(
+            repl.eval("{ proc f() int { 1 }; { var f = proc(int x) string { \"s\" }; f(3) } }")
+                .unwrap(),
+            "\"s\""
+        );
+        let errors = repl
+            .eval("{ proc f() int { 1 }; { var f = proc(int x) int { x }; f() } }")
+            .unwrap_err();
+        assert!(errors[0].contains("Número incorreto de argumentos para f"));
+    }
+
+    #[test]
+    fn test_procedure_equality_is_identity() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("proc inc(int x) int { x + 1 }").is_ok());
+        assert!(repl.eval("var f = inc").is_ok());
+        assert!(repl.eval("var g = proc(int x) int { x + 1 }").is_ok());
+        assert_eq!(repl.eval("f == inc").unwrap(), "true");
+        assert_eq!(repl.eval("g == g").unwrap(), "true");
+        assert_eq!(repl.eval("f == g").unwrap(), "false");
+        assert_eq!(repl.eval("f != g").unwrap(), "true");
+        assert_eq!(repl.eval("[f, g] == [inc, g]").unwrap(), "true");
+    }
+
+    #[test]
+    fn test_variable_and_procedure_names_in_same_scope() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("proc f() int { 1 }").is_ok());
+        let errors = repl.eval("var f = 2").unwrap_err();
+        assert!(errors[0].contains("Nome já usado por um procedimento neste escopo: f"));
+
+        assert!(repl.eval("var g = 2").is_ok());
+        let errors = repl.eval("proc g() int { 1 }").unwrap_err();
+        assert!(errors[0].contains("Nome já usado por uma variável neste escopo: g"));
+    }
+
+    #[test]
+    fn test_lambda_cannot_move_captured_variable() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        let errors = repl
+            .eval("var f = proc() { { var t = move s; write(t) } }")
+            .unwrap_err();
+        assert!(errors[0].contains("não pode mover a variável capturada 's'"));
+        assert_eq!(repl.eval("s").unwrap(), "\"oi\"");
+    }
+}
+
+#[cfg(test)]
+mod scoping_tests {
+    use plp_2025::repl::repl::Repl;
+
+    #[test]
+    fn test_procedure_sees_definition_environment() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var x = 1").is_ok());
+        assert!(repl.eval("proc f() int { x }").is_ok());
+
+        // A variável do chamador com o mesmo nome não é vista pelo corpo
+        assert_eq!(repl.eval("{ var x = 10; f() }").unwrap(), "1");
+        assert_eq!(
+            repl.eval("proc g(int x) int { f() + x }; g(100)").unwrap(),
+            "101"
+        );
+    }
+
+    #[test]
+    fn test_shadowing_after_procedure_declaration() {
+        let mut repl = Repl::new();
+        // O analisador tipa o x de f como int; a execução precisa usar o mesmo x
+        assert_eq!(
+            repl.eval("{ var x = 1; proc f() int { x }; { var x = \"s\"; f() } }")
+                .unwrap(),
+            "1"
+        );
+
+        // Procedimento declarado em um bloco e devolvido como valor
+        assert!(
+            repl.eval("proc contador() proc() -> int { { var n = 0; proc prox() int { n := n + 1; n }; prox } }")
+                .is_ok()
+        );
+        assert!(repl.eval("var c = contador()").is_ok());
+        assert!(repl.eval("var n = 100").is_ok());
+        assert_eq!(repl.eval("c() + c()").unwrap(), "3");
+    }
+
+    #[test]
+    fn test_nested_procedure_declarations() {
+        let mut repl = Repl::new();
+        assert!(
+            repl.eval("proc fat(int n) int { if n <= 1 then 1 else n * fat(n - 1) }")
+                .is_ok()
+        );
+        assert_eq!(repl.eval("fat(5)").unwrap(), "120");
+
+        assert!(
+            repl.eval(
+                "proc externo(int n) int { { proc dobro() int { n * 2 }; var n = 0; dobro() + n } }"
+            )
+            .is_ok()
+        );
+        assert_eq!(repl.eval("externo(4)").unwrap(), "8");
+    }
+
+    #[test]
+    fn test_captured_variable_cannot_be_moved_later() {
+        let mut repl = Repl::new();
+        let errors = repl
+            .eval("{ var s = \"a\"; proc f() string { s }; { var t = move s; write(f()) } }")
+            .unwrap_err();
+        assert!(
+            errors[0]
+                .contains("Variável 's' não pode ser movida: foi capturada pelo procedimento 'f'")
+        );
+
+        assert!(repl.eval("var xs: [int] = []").is_ok());
+        assert!(repl.eval("var add = proc(int x) { push(xs, x) }").is_ok());
+        let errors = repl.eval("var ys = move xs").unwrap_err();
+        assert!(errors[0].contains("foi capturada por um procedimento anônimo"));
+        assert!(repl.eval("proc consome([int] l) { skip }").is_ok());
+        let errors = repl.eval("consome(xs)").unwrap_err();
+        assert!(errors[0].contains("Variável 'xs' não pode ser movida"));
+
+        assert!(repl.eval("add(1)").is_ok());
+        assert_eq!(repl.eval("xs").unwrap(), "[1]");
+
+        // Variáveis não capturadas continuam podendo ser movidas
+        assert!(repl.eval("var zs = [2]").is_ok());
+        assert!(repl.eval("var ws = move zs").is_ok());
+    }
+
+    #[test]
+    fn test_procedure_cannot_move_captured_variable() {
+        let mut repl = Repl::new();
+        assert!(repl.eval("var s = \"oi\"").is_ok());
+        let errors = repl
+            .eval("proc f() { { var t = move s; write(t) } }")
+            .unwrap_err();
+        assert!(errors[0].contains("Procedimento 'f' não pode mover a variável capturada 's'"));
+        assert_eq!(repl.eval("s").unwrap(), "\"oi\"");
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use std::io::Write;
+    use std::process::{Command, Output, Stdio};
+
+    // Executa o binário com o programa na entrada padrão ("-")
+    fn run_stdin(command: &str, code: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_plp-2025"))
+            .args([command, "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(code.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    fn run_args(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_plp-2025"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    fn stdout(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn stderr(output: &Output) -> String {
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    }
+
+    #[test]
+    fn test_run_from_file() {
+        let path = std::env::temp_dir().join(format!("plp_cli_{}.plp", std::process::id()));
+        std::fs::write(&path, "{ var x = 2; write(x * 21) }").unwrap();
+        let output = run_args(&["run", path.to_str().unwrap()]);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(stdout(&output), "42\n");
+    }
+
+    #[test]
+    fn test_check_and_parse_do_not_execute() {
+        let output = run_stdin("check", "write(1)");
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(stdout(&output), "");
+
+        let output = run_stdin("parse", "write(1)");
+        assert_eq!(output.status.code(), Some(0));
+        assert!(stdout(&output).contains("Write"));
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        let output = run_args(&[]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr(&output).contains("Uso: plp-2025"));
+
+        let output = run_args(&["compile", "x.plp"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr(&output).contains("Comando desconhecido: compile"));
+
+        let output = run_args(&["run", "/nao/existe.plp"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr(&output).contains("Não foi possível ler '/nao/existe.plp'"));
+    }
+
+    #[test]
+    fn test_exit_codes_by_stage() {
+        let output = run_stdin("run", "write(1 +)");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(stderr(&output).contains("<stdin>"));
+
+        let output = run_stdin("check", "write(y)");
+        assert_eq!(output.status.code(), Some(3));
+
+        let output = run_stdin("run", "write(1 / 0)");
+        assert_eq!(output.status.code(), Some(4));
+        assert!(stderr(&output).contains("Divisão por zero"));
+        // O write que falhou não imprime nada
+        assert_eq!(stdout(&output), "");
+    }
+
+    #[test]
+    fn test_syntax_errors_inside_lambdas_stop_the_program() {
+        let programs = [
+            "{ var f = proc() { write(1 +); write(2) }; write(3); f() }",
+            "{ var f = proc() { skip }; f := proc() { write(1 +) }; write(3) }",
+            "{ write(3); write((proc() int { 1 + ; 2 })()) }",
+        ];
+        for code in programs {
+            let output = run_stdin("run", code);
+            assert_eq!(output.status.code(), Some(2), "{}", code);
+            assert_eq!(stdout(&output), "", "{}", code);
+            assert!(stderr(&output).contains("expected expression after binary operator"));
+        }
+    }
+}